@@ -0,0 +1,49 @@
+//! Every field lwm knows about, by identifier. The identifiers are the
+//! same ones used as label keys (see labels.rs) and by --bare.
+
+// Identifier, group (see lwm_field_group), meminfo key (or how it's
+// computed), and a short description
+#[rustfmt::skip]
+pub const LWM_FIELDS: &[(&str, &str, &str, &str)] = &[
+    ("mem_total", "ram", "MemTotal", "Total usable RAM"),
+    ("mem_free", "ram", "MemFree", "RAM that isn't used for anything"),
+    ("mem_avail", "ram", "MemAvailable", "Estimate of RAM available to new programs"),
+    ("mem_used", "ram", "MemTotal - MemAvailable", "RAM that's in use (see --used-mode)"),
+    ("mem_reclaimable", "ram", "MemAvailable - MemFree", "Used RAM the kernel can reclaim on demand"),
+    ("buffers", "cache", "Buffers", "Temporary storage for raw disk blocks"),
+    ("cached", "cache", "Cached", "Page cache (file contents, tmpfs and shmem)"),
+    ("swap_cached", "swap", "SwapCached", "Swapped out pages that are in RAM as well"),
+    ("swap_total", "swap", "SwapTotal", "Total swap space"),
+    ("swap_free", "swap", "SwapFree", "Swap space that isn't used"),
+    ("swap_used", "swap", "SwapTotal - SwapFree", "Swap space that's in use"),
+    ("zswap", "swap", "Zswap", "Memory used by the zswap compressed cache"),
+    ("zswapped", "swap", "Zswapped", "Uncompressed size of the pages stored in zswap"),
+    ("shmem", "shared", "Shmem", "Shared memory, including tmpfs"),
+    ("s_reclaimable", "kernel", "SReclaimable", "Slab memory that can be reclaimed"),
+    ("k_reclaimable", "kernel", "KReclaimable", "Kernel memory that can be reclaimed, slab included"),
+    ("anon_pages", "ram", "AnonPages", "Anonymous pages mapped into userspace"),
+    ("slab", "kernel", "Slab", "In-kernel data structures cache"),
+    ("kernel_stack", "kernel", "KernelStack", "Memory used by kernel stacks"),
+    ("page_tables", "kernel", "PageTables", "Memory used by page tables"),
+    ("hugetlb", "hugepages", "Hugetlb", "Memory consumed by huge pages of all sizes"),
+    ("dirty", "cache", "Dirty", "Memory waiting to get written back to disk"),
+    ("writeback", "cache", "Writeback", "Memory that's being written back to disk"),
+    ("vmalloc_total", "kernel", "VmallocTotal", "Size of the vmalloc address space"),
+    ("vmalloc_used", "kernel", "VmallocUsed", "Vmalloc space that's in use"),
+    ("vmalloc_chunk", "kernel", "VmallocChunk", "Largest free block of vmalloc space"),
+    ("anon_huge_pages", "hugepages", "AnonHugePages", "Anonymous transparent huge pages"),
+    ("shmem_huge_pages", "hugepages", "ShmemHugePages", "Shmem and tmpfs in transparent huge pages"),
+    ("shmem_pmd_mapped", "hugepages", "ShmemPmdMapped", "Huge page shmem mapped into userspace"),
+    ("percpu", "kernel", "Percpu", "Memory used by the per-cpu allocator"),
+    ("cma_total", "kernel", "CmaTotal", "Memory reserved for the contiguous memory allocator"),
+    ("cma_free", "kernel", "CmaFree", "Reserved CMA memory that's still free"),
+];
+
+// Group a field is shown under (ram, cache, swap, shared, kernel or
+// hugepages), None for fields that aren't in the table
+pub fn lwm_field_group(id: &str) -> Option<&'static str> {
+    LWM_FIELDS
+        .iter()
+        .find(|e| e.0 == id)
+        .map(|(_, group, _, _)| *group)
+}
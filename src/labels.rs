@@ -0,0 +1,435 @@
+//! Display labels for every row and section of the output.
+//! Rows are keyed by a stable (English) field identifier, only the
+//! label text changes between languages.
+
+use clap::ValueEnum;
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum LwmLang {
+    /// English (default)
+    En,
+
+    /// French
+    Fr,
+
+    /// German
+    De,
+}
+
+pub const LABELS_EN: &[(&str, &str)] = &[
+    ("title_memory", "Memory Information"),
+    ("mem_total", "Total Memory"),
+    ("mem_free", "Free Memory"),
+    ("mem_avail", "Avail Memory"),
+    ("mem_reclaimable", "Reclaimable"),
+    ("mem_used", "Used Memory"),
+    ("buffers", "Buffered"),
+    ("cached", "Cached"),
+    ("buff_cache", "Buff/Cache"),
+    ("swap_total", "Total Swap"),
+    ("swap_free", "Free Swap"),
+    ("swap_cached", "Cached Swap"),
+    ("swap_used", "Used Swap"),
+    ("zswap", "Total ZSwap"),
+    ("zswapped", "Commit ZSwap"),
+    ("zswap_ratio", "ZSwap Ratio"),
+    ("shmem", "Shared Memory"),
+    ("shmem_huge", "in huge pages"),
+    (
+        "shmem_note",
+        "Shared Memory includes tmpfs, /dev/shm as well",
+    ),
+    ("title_vmalloc", "Vmalloc"),
+    ("vmalloc_total", "Total Vmalloc"),
+    ("vmalloc_used", "Used Vmalloc"),
+    ("vmalloc_chunk", "Largest Chunk"),
+    ("title_thp", "Transparent HugePages"),
+    ("anon_huge_pages", "Anon HugePages"),
+    ("shmem_huge_pages", "Shmem HugePages"),
+    ("shmem_pmd_mapped", "Shmem PMD Mapped"),
+    ("percpu", "Per-CPU Memory"),
+    ("title_cma", "CMA"),
+    ("cma_total", "Reserved CMA"),
+    ("cma_free", "Free CMA"),
+    ("title_swaps", "Swap Devices"),
+    ("swap_filename", "Filename"),
+    ("swap_type", "Type"),
+    ("swap_size", "Size"),
+    ("swap_usage", "Used"),
+    ("swap_priority", "Priority"),
+    ("swap_none", "No swap devices in use"),
+    ("title_breakdown", "Memory Breakdown"),
+    ("group_ram", "RAM"),
+    ("group_cache", "Cache"),
+    ("group_swap", "Swap"),
+    ("group_shared", "Shared"),
+    ("title_delta", "Changes Since Snapshot"),
+    ("title_sum", "Total Over All Hosts"),
+    ("sum_hosts", "Hosts"),
+    ("title_hotplug", "Memory Blocks"),
+    ("hp_block_size", "Block Size"),
+    ("hp_online", "Online Blocks"),
+    ("hp_offline", "Offline Blocks"),
+    ("hp_offline_size", "Offline Memory"),
+    ("verdict", "Status"),
+    ("verdict_ok", "OK"),
+    ("verdict_warn", "WARNING"),
+    ("verdict_crit", "CRITICAL"),
+    ("verdict_low", "low memory"),
+    ("verdict_oom", "almost out of memory"),
+    ("verdict_swap", "swapping"),
+    ("verdict_swap_heavy", "swapping heavily"),
+    ("title_loadavg", "Load Average"),
+    ("load_1", "1 Minute"),
+    ("load_5", "5 Minutes"),
+    ("load_15", "15 Minutes"),
+    ("title_sysctl", "VM Tuning"),
+    ("vm_swappiness", "Swappiness"),
+    ("vm_overcommit_memory", "Overcommit Mode"),
+    ("vm_overcommit_ratio", "Overcommit Ratio"),
+    ("title_dirty", "Dirty Pressure"),
+    ("dirty", "Dirty"),
+    ("writeback", "Writeback"),
+    ("dirty_percent", "Dirty Of Available"),
+    ("vm_dirty_ratio", "Dirty Ratio"),
+    (
+        "dirty_near",
+        "Dirty memory is close to vm.dirty_ratio, writers get throttled there",
+    ),
+    ("title_zfs_arc", "ZFS ARC"),
+    ("title_hardware", "Installed Memory"),
+    ("title_iomem", "Physical Memory Map"),
+    ("iomem_ram", "System RAM"),
+    ("iomem_not_total", "Not in MemTotal"),
+    ("iomem_kernel", "Kernel Image"),
+    ("iomem_reserved", "Reserved Ranges"),
+    ("iomem_unavailable", "Not available"),
+    (
+        "iomem_note",
+        "Reserved ranges can include address space that isn't RAM (device windows)",
+    ),
+    ("title_avail", "Available Memory"),
+    ("avail_kernel", "MemAvailable"),
+    ("avail_classic", "Classic Estimate"),
+    ("avail_diff", "Difference"),
+    (
+        "avail_note",
+        "Classic estimate is MemFree + Buffers + Cached + KReclaimable (SReclaimable on older kernels)",
+    ),
+    ("hw_installed", "Installed"),
+    ("hw_usable", "Usable"),
+    ("hw_reserved", "Reserved"),
+    (
+        "hw_estimate",
+        "Installed is an estimate, MemTotal rounded up to a power of two",
+    ),
+    ("arc_size", "ARC Size"),
+    ("arc_max", "ARC Max"),
+    (
+        "arc_note",
+        "ARC is reclaimable cache, it's counted in Used Memory",
+    ),
+    ("anon_pages", "Anon Pages"),
+    ("page_cache", "Page Cache"),
+    ("slab", "Slab"),
+    ("title_kernel", "Kernel Memory"),
+    ("s_reclaimable", "Reclaimable Slab"),
+    ("k_reclaimable", "Reclaimable Kernel"),
+    ("kernel_stack", "Kernel Stack"),
+    ("page_tables", "Page Tables"),
+    ("hugetlb", "HugePages"),
+    ("other", "Other"),
+    ("title_process", "Process"),
+    ("vm_rss", "Resident Memory"),
+    ("vm_size", "Virtual Memory"),
+    ("vm_swap", "Swapped Memory"),
+    ("proc_gone", "gone"),
+];
+
+pub const LABELS_FR: &[(&str, &str)] = &[
+    ("title_memory", "Informations mémoire"),
+    ("mem_total", "Mémoire totale"),
+    ("mem_free", "Mémoire libre"),
+    ("mem_avail", "Mémoire disponible"),
+    ("mem_reclaimable", "Récupérable"),
+    ("mem_used", "Mémoire utilisée"),
+    ("buffers", "Tampons"),
+    ("cached", "Cache"),
+    ("buff_cache", "Tampons/Cache"),
+    ("swap_total", "Swap total"),
+    ("swap_free", "Swap libre"),
+    ("swap_cached", "Swap en cache"),
+    ("swap_used", "Swap utilisé"),
+    ("zswap", "ZSwap total"),
+    ("zswapped", "ZSwap engagé"),
+    ("zswap_ratio", "Taux ZSwap"),
+    ("shmem", "Mémoire partagée"),
+    ("shmem_huge", "en huge pages"),
+    (
+        "shmem_note",
+        "La mémoire partagée inclut tmpfs, /dev/shm compris",
+    ),
+    ("vmalloc_total", "Vmalloc total"),
+    ("vmalloc_used", "Vmalloc utilisé"),
+    ("vmalloc_chunk", "Plus grand bloc"),
+    ("title_thp", "HugePages transparentes"),
+    ("anon_huge_pages", "HugePages anonymes"),
+    ("shmem_huge_pages", "HugePages partagées"),
+    ("shmem_pmd_mapped", "HugePages partagées mappées"),
+    ("percpu", "Mémoire per-CPU"),
+    ("cma_total", "CMA réservée"),
+    ("cma_free", "CMA libre"),
+    ("title_swaps", "Périphériques swap"),
+    ("swap_filename", "Fichier"),
+    ("swap_size", "Taille"),
+    ("swap_usage", "Utilisé"),
+    ("swap_priority", "Priorité"),
+    ("swap_none", "Aucun périphérique swap utilisé"),
+    ("title_breakdown", "Répartition de la mémoire"),
+    ("group_ram", "Mémoire vive"),
+    ("group_shared", "Partagée"),
+    ("title_delta", "Changements depuis l'instantané"),
+    ("title_hotplug", "Blocs mémoire"),
+    ("hp_block_size", "Taille de bloc"),
+    ("hp_online", "Blocs en ligne"),
+    ("hp_offline", "Blocs hors ligne"),
+    ("hp_offline_size", "Mémoire hors ligne"),
+    ("verdict", "État"),
+    ("verdict_ok", "OK"),
+    ("verdict_warn", "AVERTISSEMENT"),
+    ("verdict_crit", "CRITIQUE"),
+    ("verdict_low", "mémoire faible"),
+    ("verdict_oom", "mémoire presque épuisée"),
+    ("verdict_swap", "utilise le swap"),
+    ("verdict_swap_heavy", "utilise beaucoup le swap"),
+    ("title_loadavg", "Charge moyenne"),
+    ("load_1", "1 minute"),
+    ("load_5", "5 minutes"),
+    ("load_15", "15 minutes"),
+    ("title_sysctl", "Réglages VM"),
+    ("vm_overcommit_memory", "Mode de surengagement"),
+    ("vm_overcommit_ratio", "Taux de surengagement"),
+    ("title_dirty", "Pression des pages sales"),
+    ("dirty", "Pages sales"),
+    ("writeback", "En écriture"),
+    ("dirty_percent", "Sales sur disponible"),
+    (
+        "dirty_near",
+        "Les pages sales approchent vm.dirty_ratio, où les écritures sont freinées",
+    ),
+    ("title_hardware", "Mémoire installée"),
+    ("title_iomem", "Carte de la mémoire physique"),
+    ("iomem_not_total", "Hors de MemTotal"),
+    ("iomem_kernel", "Image du noyau"),
+    ("iomem_reserved", "Plages réservées"),
+    ("iomem_unavailable", "Indisponible"),
+    (
+        "iomem_note",
+        "Les plages réservées peuvent inclure des adresses qui ne sont pas de la RAM (fenêtres de périphériques)",
+    ),
+    ("title_avail", "Mémoire disponible"),
+    ("avail_classic", "Estimation classique"),
+    ("avail_diff", "Différence"),
+    (
+        "avail_note",
+        "L'estimation classique est MemFree + Buffers + Cached + KReclaimable (SReclaimable sur les noyaux plus anciens)",
+    ),
+    ("hw_installed", "Installée"),
+    ("hw_usable", "Utilisable"),
+    ("hw_reserved", "Réservée"),
+    ("title_sum", "Total de tous les hôtes"),
+    ("sum_hosts", "Hôtes"),
+    ("arc_size", "Taille de l'ARC"),
+    ("arc_max", "Taille max de l'ARC"),
+    (
+        "arc_note",
+        "L'ARC est un cache récupérable, compté dans la mémoire utilisée",
+    ),
+    ("anon_pages", "Pages anonymes"),
+    ("page_cache", "Cache de pages"),
+    ("kernel_stack", "Piles noyau"),
+    ("title_kernel", "Mémoire noyau"),
+    ("s_reclaimable", "Slab récupérable"),
+    ("k_reclaimable", "Noyau récupérable"),
+    ("page_tables", "Tables de pages"),
+    ("other", "Autre"),
+    ("title_process", "Processus"),
+    ("vm_rss", "Mémoire résidente"),
+    ("vm_size", "Mémoire virtuelle"),
+    ("vm_swap", "Mémoire en swap"),
+    ("proc_gone", "terminé"),
+];
+
+pub const LABELS_DE: &[(&str, &str)] = &[
+    ("title_memory", "Speicherinformationen"),
+    ("mem_total", "Gesamtspeicher"),
+    ("mem_free", "Freier Speicher"),
+    ("mem_avail", "Verfügbarer Speicher"),
+    ("mem_reclaimable", "Freigebbar"),
+    ("mem_used", "Belegter Speicher"),
+    ("buffers", "Puffer"),
+    ("cached", "Cache"),
+    ("buff_cache", "Puffer/Cache"),
+    ("swap_total", "Swap gesamt"),
+    ("swap_free", "Freier Swap"),
+    ("swap_cached", "Zwischengespeicherter Swap"),
+    ("swap_used", "Belegter Swap"),
+    ("zswap", "ZSwap gesamt"),
+    ("zswapped", "ZSwap belegt"),
+    ("zswap_ratio", "ZSwap-Verhältnis"),
+    ("shmem", "Gemeinsamer Speicher"),
+    ("shmem_huge", "in Huge Pages"),
+    (
+        "shmem_note",
+        "Gemeinsamer Speicher enthält tmpfs, auch /dev/shm",
+    ),
+    ("vmalloc_total", "Vmalloc gesamt"),
+    ("vmalloc_used", "Belegter Vmalloc"),
+    ("vmalloc_chunk", "Größter Block"),
+    ("title_thp", "Transparente HugePages"),
+    ("anon_huge_pages", "Anonyme HugePages"),
+    ("shmem_huge_pages", "Gemeinsame HugePages"),
+    ("shmem_pmd_mapped", "Gemeinsame HugePages (gemappt)"),
+    ("percpu", "Per-CPU-Speicher"),
+    ("cma_total", "Reservierter CMA"),
+    ("cma_free", "Freier CMA"),
+    ("title_swaps", "Swap-Geräte"),
+    ("swap_filename", "Dateiname"),
+    ("swap_type", "Typ"),
+    ("swap_size", "Größe"),
+    ("swap_usage", "Belegt"),
+    ("swap_priority", "Priorität"),
+    ("swap_none", "Keine Swap-Geräte in Benutzung"),
+    ("group_ram", "Arbeitsspeicher"),
+    ("group_shared", "Gemeinsam"),
+    ("title_breakdown", "Speicheraufteilung"),
+    ("title_delta", "Änderungen seit dem Snapshot"),
+    ("title_hotplug", "Speicherblöcke"),
+    ("hp_block_size", "Blockgröße"),
+    ("hp_online", "Aktive Blöcke"),
+    ("hp_offline", "Inaktive Blöcke"),
+    ("hp_offline_size", "Inaktiver Speicher"),
+    ("verdict", "Status"),
+    ("verdict_ok", "OK"),
+    ("verdict_warn", "WARNUNG"),
+    ("verdict_crit", "KRITISCH"),
+    ("verdict_low", "wenig Speicher"),
+    ("verdict_oom", "Speicher fast erschöpft"),
+    ("verdict_swap", "lagert aus"),
+    ("verdict_swap_heavy", "lagert stark aus"),
+    ("title_loadavg", "Durchschnittliche Last"),
+    ("load_1", "1 Minute"),
+    ("load_5", "5 Minuten"),
+    ("load_15", "15 Minuten"),
+    ("title_sysctl", "VM-Einstellungen"),
+    ("vm_overcommit_memory", "Overcommit-Modus"),
+    ("vm_overcommit_ratio", "Overcommit-Verhältnis"),
+    ("title_dirty", "Dirty-Druck"),
+    ("writeback", "Im Rückschreiben"),
+    ("dirty_percent", "Dirty vom Verfügbaren"),
+    (
+        "dirty_near",
+        "Dirty-Speicher nähert sich vm.dirty_ratio, dort werden Schreiber gebremst",
+    ),
+    ("title_hardware", "Installierter Speicher"),
+    ("title_iomem", "Physische Speicherbelegung"),
+    ("iomem_not_total", "Nicht in MemTotal"),
+    ("iomem_kernel", "Kernel-Image"),
+    ("iomem_reserved", "Reservierte Bereiche"),
+    ("iomem_unavailable", "Nicht verfügbar"),
+    (
+        "iomem_note",
+        "Reservierte Bereiche können Adressraum ohne RAM enthalten (Gerätefenster)",
+    ),
+    ("title_avail", "Verfügbarer Speicher"),
+    ("avail_classic", "Klassische Schätzung"),
+    ("avail_diff", "Differenz"),
+    (
+        "avail_note",
+        "Die klassische Schätzung ist MemFree + Buffers + Cached + KReclaimable (SReclaimable bei älteren Kerneln)",
+    ),
+    ("hw_installed", "Installiert"),
+    ("hw_usable", "Nutzbar"),
+    ("hw_reserved", "Reserviert"),
+    ("title_sum", "Summe aller Hosts"),
+    ("sum_hosts", "Hosts"),
+    ("arc_size", "ARC-Größe"),
+    ("arc_max", "Maximale ARC-Größe"),
+    (
+        "arc_note",
+        "Der ARC ist freigebbarer Cache und zählt zum belegten Speicher",
+    ),
+    ("anon_pages", "Anonyme Seiten"),
+    ("page_cache", "Seitencache"),
+    ("kernel_stack", "Kernel-Stacks"),
+    ("title_kernel", "Kernel-Speicher"),
+    ("s_reclaimable", "Freigebbarer Slab"),
+    ("k_reclaimable", "Freigebbarer Kernel-Speicher"),
+    ("page_tables", "Seitentabellen"),
+    ("other", "Sonstiges"),
+    ("title_process", "Prozess"),
+    ("vm_rss", "Residenter Speicher"),
+    ("vm_size", "Virtueller Speicher"),
+    ("vm_swap", "Ausgelagerter Speicher"),
+    ("proc_gone", "beendet"),
+];
+
+// Abbreviated English labels for narrow terminals (--compact)
+pub const LABELS_COMPACT: &[(&str, &str)] = &[
+    ("title_memory", "Memory"),
+    ("mem_total", "Tot"),
+    ("mem_free", "Free"),
+    ("mem_avail", "Avail"),
+    ("mem_reclaimable", "Recl"),
+    ("mem_used", "Used"),
+    ("buffers", "Buf"),
+    ("cached", "Cache"),
+    ("buff_cache", "Buf/Cache"),
+    ("swap_total", "Swp Tot"),
+    ("swap_free", "Swp Free"),
+    ("swap_cached", "Swp Cache"),
+    ("swap_used", "Swp Used"),
+    ("zswap", "ZSwp"),
+    ("zswapped", "ZSwp Commit"),
+    ("zswap_ratio", "ZSwp Ratio"),
+    ("shmem", "Shm"),
+    ("title_kernel", "Kernel"),
+    ("s_reclaimable", "SRecl"),
+    ("k_reclaimable", "KRecl"),
+    ("kernel_stack", "Stack"),
+    ("page_tables", "PgTbl"),
+    ("vmalloc_total", "Tot"),
+    ("vmalloc_used", "Used"),
+    ("vmalloc_chunk", "Chunk"),
+    ("title_thp", "THP"),
+    ("anon_huge_pages", "Anon"),
+    ("shmem_huge_pages", "Shm"),
+    ("shmem_pmd_mapped", "Shm PMD"),
+    ("percpu", "PerCPU"),
+    ("cma_total", "Rsvd"),
+    ("cma_free", "Free"),
+];
+
+// Built-in labels of a language, partial for anything but English
+pub fn lwm_builtin_labels(lang: LwmLang) -> &'static [(&'static str, &'static str)] {
+    match lang {
+        LwmLang::En => LABELS_EN,
+        LwmLang::Fr => LABELS_FR,
+        LwmLang::De => LABELS_DE,
+    }
+}
+
+// Parse a key=value labels file, blank lines and # comments are skipped
+pub fn lwm_parse_labels(src: &str) -> Result<Vec<(String, String)>, String> {
+    src.lines()
+        .enumerate()
+        .map(|(i, e)| (i + 1, e.trim()))
+        .filter(|(_, e)| !e.is_empty() && !e.starts_with('#'))
+        .map(|(i, e)| match e.split_once('=') {
+            Some((key, value)) if !key.trim().is_empty() => {
+                Ok((key.trim().to_string(), value.trim().to_string()))
+            }
+            _ => Err(format!("line {i}: expected key=value")),
+        })
+        .collect()
+}
@@ -4,12 +4,41 @@
 #![allow(non_upper_case_globals)]
 #![cfg(target_os = "linux")]
 
-use clap::Parser;
+mod fields;
+mod labels;
+mod selftest;
+
+use clap::{error::ErrorKind, CommandFactory, Parser, ValueEnum};
+use fields::{lwm_field_group, LWM_FIELDS};
+use labels::{lwm_builtin_labels, lwm_parse_labels, LwmLang, LABELS_COMPACT};
+use std::collections::HashMap;
 use std::fs;
+use std::io::{BufRead, BufReader, IsTerminal, Write};
+use std::os::unix::net::UnixDatagram;
+use std::process;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 const MEMINFO_PATH: &str = "/proc/meminfo";
+const SWAPS_PATH: &str = "/proc/swaps";
+const SYSLOG_PATH: &str = "/dev/log";
+const ARCSTATS_PATH: &str = "/proc/spl/kstat/zfs/arcstats";
+const MEMORY_BLOCKS_PATH: &str = "/sys/devices/system/memory";
+const IOMEM_PATH: &str = "/proc/iomem";
+const HOSTNAME_PATH: &str = "/proc/sys/kernel/hostname";
+const SYSCTL_VM_PATH: &str = "/proc/sys/vm";
+const LOADAVG_PATH: &str = "/proc/loadavg";
+
+// Where --links points field labels, the kernel doc has no anchor per key
+const MEMINFO_DOC_URL: &str = "https://docs.kernel.org/filesystems/proc.html#meminfo";
 const WHITE_COLOR: &str = "\x1b[1;37m";
+const GREEN_COLOR: &str = "\x1b[1;32m";
+const YELLOW_COLOR: &str = "\x1b[1;33m";
+const RED_COLOR: &str = "\x1b[1;31m";
+const MAGENTA_COLOR: &str = "\x1b[1;35m";
+const BLUE_COLOR: &str = "\x1b[1;34m";
+const ORANGE_COLOR: &str = "\x1b[1;38;5;208m";
 const END_COLOR: &str = "\x1b[0m";
+const CLEAR_SCREEN: &str = "\x1b[2J\x1b[H";
 
 // Convert to bytes
 macro_rules! to_bytes {
@@ -25,20 +54,44 @@ macro_rules! to_size {
     };
 }
 
-// Binary system
-const TO_B: f64 = 1.0;
-const TO_KB: f64 = 1024.0;
-const TO_MB: f64 = TO_KB * 1024.0;
-const TO_GB: f64 = TO_MB * 1024.0;
-const TO_TB: f64 = TO_GB * 1024.0;
-const TO_PB: f64 = TO_TB * 1024.0;
+// --verdict cut-offs, available memory and used swap as percentages
+const VERDICT_AVAIL_WARN: f64 = 15.0;
+const VERDICT_AVAIL_CRIT: f64 = 5.0;
+const VERDICT_SWAP_WARN: f64 = 50.0;
+const VERDICT_SWAP_CRIT: f64 = 80.0;
+
+// --dirty warns once Dirty reaches this fraction of vm.dirty_ratio
+const DIRTY_NEAR: f64 = 0.8;
 
 // Decimal system
-const TO_KiB: f64 = 1000.0;
-const TO_MiB: f64 = TO_KiB * 1000.0;
-const TO_GiB: f64 = TO_MiB * 1000.0;
-const TO_TiB: f64 = TO_GiB * 1000.0;
-const TO_PiB: f64 = TO_TiB * 1000.0;
+const TO_B: f64 = 1.0;
+const TO_KB: f64 = 1000.0;
+const TO_MB: f64 = TO_KB * 1000.0;
+const TO_GB: f64 = TO_MB * 1000.0;
+const TO_TB: f64 = TO_GB * 1000.0;
+const TO_PB: f64 = TO_TB * 1000.0;
+
+// Binary system
+const TO_KiB: f64 = 1024.0;
+const TO_MiB: f64 = TO_KiB * 1024.0;
+const TO_GiB: f64 = TO_MiB * 1024.0;
+const TO_TiB: f64 = TO_GiB * 1024.0;
+const TO_PiB: f64 = TO_TiB * 1024.0;
+
+// Every fixed unit, with the flag that selects it
+const LWM_UNITS: [(&str, &str, f64); 11] = [
+    ("B", "--bytes", TO_B),
+    ("KB", "--kilo", TO_KB),
+    ("KiB", "--kibi", TO_KiB),
+    ("MB", "--mega", TO_MB),
+    ("MiB", "--mibi", TO_MiB),
+    ("GB", "--giga", TO_GB),
+    ("GiB", "--gibi", TO_GiB),
+    ("TB", "--tera", TO_TB),
+    ("TiB", "--tibi", TO_TiB),
+    ("PB", "--peta", TO_PB),
+    ("PiB", "--pibi", TO_PiB),
+];
 
 // Lwm low memory
 struct Lwm {
@@ -61,6 +114,8 @@ struct Lwm {
     cached: u64,
 
     /// Swap cached memory (to the disk)
+    /// Pages that were swapped out and read back in, but are still in the
+    /// swap area as well, so they're counted in both RAM and swap
     swap_cached: u64,
 
     /// Total allocable swap memory
@@ -83,6 +138,253 @@ struct Lwm {
 
     /// Reclaimable slab memory
     s_reclaimable: u64,
+
+    /// Reclaimable kernel memory, slab included (newer kernels only)
+    k_reclaimable: u64,
+
+    /// Whether the kernel reports KReclaimable at all
+    has_kreclaimable: bool,
+
+    /// Anonymous (non file-backed) pages mapped into userspace
+    anon_pages: u64,
+
+    /// In-kernel data structures cache
+    slab: u64,
+
+    /// Memory used by kernel stacks
+    kernel_stack: u64,
+
+    /// Memory used by page tables
+    page_tables: u64,
+
+    /// Memory consumed by huge pages of all sizes
+    hugetlb: u64,
+
+    /// Memory waiting to get written back to disk
+    dirty: u64,
+
+    /// Memory that's being written back to disk
+    writeback: u64,
+
+    /// Size of the vmalloc address space
+    vmalloc_total: u64,
+
+    /// Vmalloc space that's in use
+    vmalloc_used: u64,
+
+    /// Largest contiguous free block of vmalloc space
+    vmalloc_chunk: u64,
+
+    /// Whether the kernel reports vmalloc information at all
+    has_vmalloc: bool,
+
+    /// Anonymous memory backed by transparent huge pages
+    anon_huge_pages: u64,
+
+    /// Shared memory (and tmpfs) backed by transparent huge pages
+    shmem_huge_pages: u64,
+
+    /// Huge page backed shared memory that's mapped into userspace
+    shmem_pmd_mapped: u64,
+
+    /// Whether the kernel reports transparent huge pages at all
+    has_thp: bool,
+
+    /// Memory reserved for the per-cpu allocator
+    percpu: u64,
+
+    /// Whether the kernel reports Percpu at all
+    has_percpu: bool,
+
+    /// Memory reserved for the contiguous memory allocator
+    cma_total: u64,
+
+    /// Reserved CMA memory that's still free
+    cma_free: u64,
+
+    /// Whether the kernel reports CMA at all, mostly ARM boards
+    has_cma: bool,
+
+    /// Display labels, keyed by field identifier
+    labels: HashMap<String, String>,
+
+    /// Whether values (and not only labels) get colored
+    color_values: bool,
+
+    /// Colors values get with color_values
+    palette: LwmPalette,
+
+    /// Whether Shared Memory gets its huge page and tmpfs notes
+    shmem_detail: bool,
+
+    /// Title of the main section instead of its label
+    title: Option<String>,
+
+    /// How mem_used is computed
+    used_mode: LwmUsed,
+
+    /// Plain titles and abbreviated labels, for narrow terminals
+    compact: bool,
+
+    /// Whether the screen is cleared before the output
+    clear: bool,
+
+    /// Values as fractions of their total, with this many decimals
+    normalized: Option<usize>,
+
+    /// Rows aligned in a table, unit suffixes in their own column
+    auto_unit: bool,
+
+    /// Whether friendly values also show their exact byte count
+    with_bytes: bool,
+
+    /// Journal field to read meminfo from, instead of /proc/meminfo
+    from_journal: Option<String>,
+
+    /// Whether the main section gets a subheader per group of fields
+    groups: bool,
+
+    /// Whether colored field labels link to the meminfo documentation
+    links: bool,
+
+    /// Friendly swap usage below this (in kilobytes) shows as zero
+    swap_floor: u64,
+}
+
+// A swap device, as listed in /proc/swaps
+struct LwmSwap {
+    /// Path of the swap partition or file
+    filename: String,
+
+    /// Swap type (partition, file)
+    kind: String,
+
+    /// Size of the swap device
+    size: u64,
+
+    /// Swap that's being used on this device
+    used: u64,
+
+    /// Priority of the device (higher is used first)
+    priority: i32,
+}
+
+// Memory of a single process, as listed in /proc/<pid>/status
+struct LwmProc {
+    /// Process ID
+    pid: u32,
+
+    /// Command name of the process
+    name: String,
+
+    /// Resident set size, memory that's in RAM
+    vm_rss: u64,
+
+    /// Virtual memory size
+    vm_size: u64,
+
+    /// Memory of the process that's swapped out
+    vm_swap: u64,
+}
+
+// How a value (in kilobytes) gets displayed
+#[derive(Clone, Copy, Debug)]
+enum LwmUnit {
+    /// Human-readable, binary or decimal, optionally without rounding
+    /// or with single letter suffixes
+    Friendly {
+        binary: bool,
+        round: bool,
+        short: bool,
+    },
+
+    /// Fixed size, one of the TO_* constants
+    Size(f64),
+}
+
+// What --percent-only reports the usage of
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum LwmPercent {
+    /// Used memory
+    Mem,
+
+    /// Used swap
+    Swap,
+}
+
+// What counts as used memory
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum LwmUsed {
+    /// MemTotal - MemAvailable
+    Available,
+
+    /// MemTotal - MemFree
+    Free,
+
+    /// MemTotal - MemFree - Buffers - Cached - SReclaimable
+    Cache,
+}
+
+// What the output looks like, --output-format
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum LwmFormat {
+    /// Labelled sections (the default)
+    Pretty,
+
+    /// Only the values, space-separated on a single line (see --bare)
+    Bare,
+
+    /// The column layout of free(1)
+    Free,
+
+    /// An InfluxDB line protocol point, in bytes and tagged with the hostname
+    Influx,
+
+    /// The main rows as field=value pairs on one line, like --syslog sends
+    Oneline,
+}
+
+// Colors of --color-values
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum LwmPalette {
+    /// Green and yellow
+    Default,
+
+    /// Blue and orange, distinguishable with red-green color blindness
+    Colorblind,
+}
+
+// Syslog facilities lwm can log under
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum LwmFacility {
+    User,
+    Daemon,
+    Local0,
+    Local1,
+    Local2,
+    Local3,
+    Local4,
+    Local5,
+    Local6,
+    Local7,
+}
+
+impl LwmFacility {
+    // Facility number, as defined in syslog.h
+    fn lwm_code(self) -> u8 {
+        match self {
+            LwmFacility::User => 1,
+            LwmFacility::Daemon => 3,
+            LwmFacility::Local0 => 16,
+            LwmFacility::Local1 => 17,
+            LwmFacility::Local2 => 18,
+            LwmFacility::Local3 => 19,
+            LwmFacility::Local4 => 20,
+            LwmFacility::Local5 => 21,
+            LwmFacility::Local6 => 22,
+            LwmFacility::Local7 => 23,
+        }
+    }
 }
 
 #[derive(Parser, Debug)]
@@ -91,10 +393,19 @@ struct LwmArgs {
     #[arg(short, long)]
     all: bool,
 
-    /// Disable output colors
+    /// Disable output colors (also disabled when TERM is dumb, empty or unset)
     #[arg(short, long)]
     no_color: bool,
 
+    /// Color values as well, free memory in green and used in yellow
+    #[arg(long)]
+    color_values: bool,
+
+    /// Colors used by --color-values, --verdict, --dirty and the delta arrows
+    /// of --delta-since and --compare
+    #[arg(long, value_enum, default_value = "default")]
+    palette: LwmPalette,
+
     /// Calculate in binary
     #[arg(short, long)]
     binary: bool,
@@ -103,6 +414,41 @@ struct LwmArgs {
     #[arg(short, long)]
     friendly: bool,
 
+    /// Pick the unit of every value like --friendly, in an aligned table
+    #[arg(long)]
+    auto_unit: bool,
+
+    /// Show the exact byte count next to friendly values
+    #[arg(long)]
+    with_bytes: bool,
+
+    /// Make field labels links to the kernel's meminfo documentation
+    ///
+    /// Uses OSC 8 hyperlinks, only with colors. Terminals without them
+    /// show the plain label.
+    #[arg(long)]
+    links: bool,
+
+    /// Show friendly swap usage below this size as 0B
+    #[arg(long, value_name = "SIZE", default_value = "1MiB")]
+    swap_noise_floor: String,
+
+    /// Show friendly swap usage as is, however small
+    #[arg(long)]
+    exact: bool,
+
+    /// List the main section as one flat list, without group subheaders
+    #[arg(long)]
+    no_groups: bool,
+
+    /// Don't round friendly output to a single decimal
+    #[arg(long)]
+    no_round: bool,
+
+    /// Single letter suffixes (K, M, G...) in friendly output
+    #[arg(long)]
+    short_suffix: bool,
+
     /// Print memory information in bytes
     #[arg(long)]
     bytes: bool,
@@ -146,6 +492,412 @@ struct LwmArgs {
     /// Print memory information in petabytes
     #[arg(long)]
     pibi: bool,
+
+    /// Rank what the installed memory is being used for
+    #[arg(long)]
+    breakdown: bool,
+
+    /// Fold breakdown buckets smaller than this percentage into Other
+    #[arg(long, value_name = "P", default_value_t = 0.0)]
+    min_percent: f64,
+
+    /// Show buffers, cache and their sum as separate rows (like free -w)
+    #[arg(short, long)]
+    wide: bool,
+
+    /// Print a per-device breakdown of /proc/swaps
+    #[arg(long)]
+    swaps: bool,
+
+    /// Language of the output labels
+    #[arg(long, value_enum, default_value = "en")]
+    lang: LwmLang,
+
+    /// Override output labels from a key=value file
+    #[arg(long, value_name = "FILE")]
+    labels: Option<String>,
+
+    /// Print only the used percentage of memory (or swap) and exit
+    #[arg(long, value_enum, value_name = "WHICH", num_args = 0..=1, default_missing_value = "mem")]
+    percent_only: Option<LwmPercent>,
+
+    /// Output format, replaces --bare, --free-compat and --influx
+    ///
+    /// Only pretty goes with the views that have their own layout
+    /// (--pid, --compare, --delta-since, --sum, --batch, --breakdown,
+    /// --swaps, --percent-only and --syslog without --also-stdout).
+    #[arg(long, value_enum, value_name = "FORMAT", group = "format")]
+    output_format: Option<LwmFormat>,
+
+    /// Print in the exact column layout of free(1) (deprecated, see --output-format)
+    #[arg(long, group = "format")]
+    free_compat: bool,
+
+    /// List every field with its label, meminfo source and description
+    #[arg(long)]
+    help_fields: bool,
+
+    /// Print one field in every fixed unit, to check the conversions
+    #[arg(long, value_name = "FIELD")]
+    explain_unit: Option<String>,
+
+    /// Check the parser against the built-in meminfo samples
+    #[arg(long, hide = true)]
+    selftest: bool,
+
+    /// Check that friendly values of the current meminfo parse back as sizes
+    #[arg(long, hide = true)]
+    round_trip_check: bool,
+
+    /// Time this many parses of each built-in meminfo sample
+    #[arg(long, hide = true, value_name = "ITERATIONS", value_parser = clap::value_parser!(u64).range(1..))]
+    bench_parse: Option<u64>,
+
+    /// Show how memory changed since a saved meminfo snapshot
+    #[arg(long, value_name = "FILE")]
+    delta_since: Option<String>,
+
+    /// Read meminfo from the newest journal entry with this field
+    #[arg(long, value_name = "FIELD")]
+    from_journal: Option<String>,
+
+    /// Show every meminfo file in turn, in filename order (e.g. captured samples)
+    #[arg(long, value_name = "FILE", num_args = 1..)]
+    batch: Option<Vec<String>>,
+
+    /// Add up the meminfo of several hosts, e.g. collected from a cluster
+    #[arg(long, value_name = "FILE", num_args = 1..)]
+    sum: Option<Vec<String>>,
+
+    /// Show values as fractions (0 to 1) of MemTotal, swap values of the swap total
+    #[arg(long)]
+    normalized: bool,
+
+    /// Decimals of --normalized values
+    #[arg(long, value_name = "N", default_value_t = 3, requires = "normalized")]
+    precision: usize,
+
+    /// Print only the values, space-separated on a single line
+    ///
+    /// No labels, box or colors. The values are always in this order:
+    /// mem_total mem_free mem_avail mem_used buffers swap_total swap_free
+    /// swap_cached swap_used zswap zswapped shmem
+    ///
+    /// Deprecated, same as --output-format bare
+    #[arg(long, verbatim_doc_comment, group = "format")]
+    bare: bool,
+
+    /// What counts as used memory, for Used Memory and the usage percentage
+    #[arg(long, value_enum, default_value = "available")]
+    used_mode: LwmUsed,
+
+    /// Title of the memory section, e.g. to tell hosts apart in a report
+    #[arg(long, value_name = "TEXT")]
+    title: Option<String>,
+
+    /// Exit with 1 unless a condition holds, e.g. 'mem_avail > 2GiB' or 'swap_used < 10%'
+    ///
+    /// Prints nothing when the condition holds. Operators are <, <=, >, >= and ==,
+    /// percentages are of the swap total for swap fields and of MemTotal otherwise.
+    #[arg(long, value_name = "CONDITION")]
+    assert: Option<String>,
+
+    /// Use the hostname as the title of the memory section
+    #[arg(long, conflicts_with = "title")]
+    title_hostname: bool,
+
+    /// Abbreviated labels and no boxes (the default on terminals too narrow)
+    #[arg(long)]
+    compact: bool,
+
+    /// Show how much shared memory is in huge pages, and that it includes tmpfs
+    #[arg(long)]
+    shmem_detail: bool,
+
+    /// Show installed RAM next to the usable total, and what firmware reserves
+    #[arg(long)]
+    hardware_total: bool,
+
+    /// Show reserved and kernel image regions from /proc/iomem (needs root)
+    #[arg(long)]
+    iomem_reserved: bool,
+
+    /// Show MemAvailable next to the classic free + buffers + cached estimate
+    #[arg(long)]
+    show_both_avail: bool,
+
+    /// Show how many memory blocks are online and offline (memory hotplug)
+    #[arg(long)]
+    hotplug: bool,
+
+    /// End with a one-line verdict: OK, WARNING or CRITICAL
+    ///
+    /// WARNING below 15% available memory or above 50% used swap,
+    /// CRITICAL below 5% available or above 80% used swap.
+    #[arg(long, verbatim_doc_comment)]
+    verdict: bool,
+
+    /// Show the 1, 5 and 15 minute load averages
+    #[arg(long)]
+    loadavg: bool,
+
+    /// Show vm.swappiness and the overcommit settings
+    #[arg(long)]
+    sysctl: bool,
+
+    /// Show Dirty and Writeback, and how close Dirty is to vm.dirty_ratio
+    #[arg(long)]
+    dirty: bool,
+
+    /// Show the ZFS ARC size (skipped on systems without ZFS)
+    #[arg(long)]
+    zfs_arc: bool,
+
+    /// Print an InfluxDB line protocol point (deprecated, see --output-format)
+    #[arg(long, group = "format")]
+    influx: bool,
+
+    /// Send a one-line summary to syslog instead of printing it
+    #[arg(long)]
+    syslog: bool,
+
+    /// Syslog facility to log under
+    #[arg(long, value_enum, default_value = "user")]
+    syslog_facility: LwmFacility,
+
+    /// Syslog tag (program name) to log with
+    #[arg(long, default_value = "lwm")]
+    syslog_tag: String,
+
+    /// Print the usual output as well when logging to syslog
+    #[arg(long)]
+    also_stdout: bool,
+
+    /// Show the memory of a single process instead
+    #[arg(long, value_name = "PID")]
+    pid: Option<u32>,
+
+    /// Compare the memory of two processes
+    #[arg(long, num_args = 2, value_names = ["PID1", "PID2"])]
+    compare: Option<Vec<u32>>,
+
+    /// Clear the screen before printing (only on a terminal)
+    #[arg(long)]
+    clear: bool,
+
+    /// Don't warn about flags that have no effect
+    #[arg(short, long)]
+    quiet: bool,
+}
+
+impl LwmArgs {
+    // Unit flags that were passed, in the order they take precedence
+    fn lwm_size_flags(&self) -> Vec<&'static str> {
+        [
+            ("--bytes", self.bytes),
+            ("--kilo", self.kilo),
+            ("--kibi", self.kibi),
+            ("--mega", self.mega),
+            ("--mibi", self.mibi),
+            ("--giga", self.giga),
+            ("--gibi", self.gibi),
+            ("--tera", self.tera),
+            ("--tibi", self.tibi),
+            ("--peta", self.peta),
+            ("--pibi", self.pibi),
+        ]
+        .iter()
+        .filter(|e| e.1)
+        .map(|e| e.0)
+        .collect()
+    }
+
+    // The output format, from --output-format or one of the flags it replaces
+    fn lwm_format(&self) -> LwmFormat {
+        if let Some(format) = self.output_format {
+            format
+        } else if self.influx {
+            LwmFormat::Influx
+        } else if self.free_compat {
+            LwmFormat::Free
+        } else if self.bare {
+            LwmFormat::Bare
+        } else {
+            LwmFormat::Pretty
+        }
+    }
+
+    // The flag that picked the output format, for error messages
+    fn lwm_format_flag(&self) -> String {
+        match self.output_format {
+            Some(format) => format!(
+                "--output-format {}",
+                format
+                    .to_possible_value()
+                    .map_or(String::new(), |e| e.get_name().to_string())
+            ),
+            None if self.influx => "--influx".to_string(),
+            None if self.free_compat => "--free-compat".to_string(),
+            None if self.bare => "--bare".to_string(),
+            None => String::new(),
+        }
+    }
+
+    // A view with a layout of its own, which a format other than pretty
+    // can't apply to
+    fn lwm_format_conflict(&self) -> Option<&'static str> {
+        if self.lwm_format() == LwmFormat::Pretty {
+            return None;
+        }
+
+        [
+            ("--pid", self.pid.is_some()),
+            ("--compare", self.compare.is_some()),
+            ("--delta-since", self.delta_since.is_some()),
+            ("--sum", self.sum.is_some()),
+            ("--batch", self.batch.is_some()),
+            ("--breakdown", self.breakdown),
+            ("--swaps", self.swaps),
+            ("--percent-only", self.percent_only.is_some()),
+            ("--syslog", self.syslog && !self.also_stdout),
+        ]
+        .iter()
+        .find(|e| e.1)
+        .map(|e| e.0)
+    }
+
+    // Flags that have no effect given the other flags
+    fn lwm_ignored_flags(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        let sizes = self.lwm_size_flags();
+
+        if self.all && !sizes.is_empty() {
+            warnings.push(format!(
+                "{} ignored, --all doesn't use unit flags",
+                sizes.join(", ")
+            ));
+        } else if sizes.len() > 1 {
+            warnings.push(format!(
+                "{} ignored, {} takes precedence",
+                sizes[1..].join(", "),
+                sizes[0]
+            ));
+        }
+
+        let is_frndly = (self.friendly || self.auto_unit) && (self.all || sizes.is_empty());
+        if self.friendly && !is_frndly {
+            warnings.push(format!("--friendly ignored, {} selects the unit", sizes[0]));
+        }
+        if self.auto_unit && !is_frndly {
+            warnings.push(format!(
+                "--auto-unit ignored, {} selects the unit",
+                sizes[0]
+            ));
+        }
+        if self.binary && !is_frndly {
+            warnings.push("--binary ignored, it only applies to --friendly output".to_string());
+        }
+        if self.with_bytes && !is_frndly {
+            warnings.push("--with-bytes ignored, it only applies to --friendly output".to_string());
+        }
+        if self.exact && !is_frndly {
+            warnings.push("--exact ignored, it only applies to --friendly output".to_string());
+        }
+        if self.no_round && !is_frndly {
+            warnings.push("--no-round ignored, it only applies to --friendly output".to_string());
+        }
+        if self.color_values && self.no_color {
+            warnings.push("--color-values ignored, --no-color disables colors".to_string());
+        }
+        if self.links && self.no_color {
+            warnings.push("--links ignored, --no-color disables escapes".to_string());
+        }
+        if self.palette != LwmPalette::Default {
+            let uses_palette = self.color_values
+                || self.verdict
+                || self.dirty
+                || self.delta_since.is_some()
+                || self.compare.is_some();
+            if self.no_color {
+                warnings.push("--palette ignored, --no-color disables colors".to_string());
+            } else if !uses_palette {
+                warnings.push(
+                    "--palette ignored, it only applies to --color-values, --verdict, --dirty, \
+                     --delta-since and --compare"
+                        .to_string(),
+                );
+            }
+        }
+        if self.min_percent > 0.0 && !self.breakdown {
+            warnings.push("--min-percent ignored, it only applies to --breakdown".to_string());
+        }
+        if self.short_suffix && !is_frndly {
+            warnings
+                .push("--short-suffix ignored, it only applies to --friendly output".to_string());
+        }
+
+        warnings
+    }
+
+    // A dumb terminal (or an empty or unset TERM) must never get escape sequences
+    fn lwm_escapes(&self) -> bool {
+        !matches!(
+            std::env::var("TERM").as_deref(),
+            Err(_) | Ok("dumb") | Ok("")
+        )
+    }
+
+    fn lwm_color(&self) -> bool {
+        !self.no_color && self.lwm_escapes()
+    }
+
+    fn lwm_friendly(&self) -> LwmUnit {
+        LwmUnit::Friendly {
+            binary: self.binary,
+            round: !self.no_round,
+            short: self.short_suffix,
+        }
+    }
+
+    // Pick the unit requested by the flags (bytes by default)
+    fn lwm_unit(&self) -> LwmUnit {
+        // --all ignores the unit flags
+        if self.all {
+            return if self.friendly || self.auto_unit {
+                self.lwm_friendly()
+            } else {
+                LwmUnit::Size(TO_B)
+            };
+        }
+
+        if self.bytes {
+            LwmUnit::Size(TO_B)
+        } else if self.kilo {
+            LwmUnit::Size(TO_KB)
+        } else if self.kibi {
+            LwmUnit::Size(TO_KiB)
+        } else if self.mega {
+            LwmUnit::Size(TO_MB)
+        } else if self.mibi {
+            LwmUnit::Size(TO_MiB)
+        } else if self.giga {
+            LwmUnit::Size(TO_GB)
+        } else if self.gibi {
+            LwmUnit::Size(TO_GiB)
+        } else if self.tera {
+            LwmUnit::Size(TO_TB)
+        } else if self.tibi {
+            LwmUnit::Size(TO_TiB)
+        } else if self.peta {
+            LwmUnit::Size(TO_PB)
+        } else if self.pibi {
+            LwmUnit::Size(TO_PiB)
+        } else if self.friendly || self.auto_unit {
+            self.lwm_friendly()
+        } else {
+            LwmUnit::Size(TO_B)
+        }
+    }
 }
 
 impl Lwm {
@@ -165,240 +917,1807 @@ impl Lwm {
             zswapped: 0,
             shmem: 0,
             s_reclaimable: 0,
+            k_reclaimable: 0,
+            has_kreclaimable: false,
+            anon_pages: 0,
+            slab: 0,
+            kernel_stack: 0,
+            page_tables: 0,
+            hugetlb: 0,
+            dirty: 0,
+            writeback: 0,
+            vmalloc_total: 0,
+            vmalloc_used: 0,
+            vmalloc_chunk: 0,
+            has_vmalloc: false,
+            anon_huge_pages: 0,
+            shmem_huge_pages: 0,
+            shmem_pmd_mapped: 0,
+            has_thp: false,
+            percpu: 0,
+            has_percpu: false,
+            cma_total: 0,
+            cma_free: 0,
+            has_cma: false,
+            labels: HashMap::new(),
+            color_values: false,
+            palette: LwmPalette::Default,
+            shmem_detail: false,
+            title: None,
+            used_mode: LwmUsed::Available,
+            compact: false,
+            clear: false,
+            normalized: None,
+            auto_unit: false,
+            with_bytes: false,
+            from_journal: None,
+            groups: false,
+            links: false,
+            swap_floor: 0,
         }
     }
 
     #[inline]
-    fn lwm_read_file(&self) -> String {
-        fs::read_to_string(MEMINFO_PATH).unwrap()
+    fn lwm_read_file(&self) -> Result<String, String> {
+        fs::read_to_string(MEMINFO_PATH).map_err(|e| format!("{MEMINFO_PATH}: {e}"))
+    }
+
+    // Everything after the colon of the line with exactly this key, so
+    // "Cached:" never picks up "SwapCached:"
+    fn lwm_find_value<'a>(&self, src: &'a str, key: &str) -> Option<&'a str> {
+        let key = key.trim_end_matches(':');
+
+        src.lines()
+            .map(|e| self.lwm_strip_node(e))
+            .filter_map(|e| e.split_once(':'))
+            .find(|(name, _)| name.trim() == key)
+            .map(|(_, value)| value)
+    }
+
+    // A value in kilobytes, None if it doesn't parse cleanly (e.g. a truncated line).
+    // The kernel always writes kB, some /proc shims write KB, kb or bytes
+    fn lwm_parse_value(&self, second: &str) -> Option<u64> {
+        let second = second.trim();
+        let (number, suffix) = second.split_at(
+            second
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(second.len()),
+        );
+        let number = number.parse::<u64>().ok()?;
+
+        match suffix.trim().to_ascii_lowercase().as_str() {
+            "" | "kb" => Some(number),
+            "b" => Some(number / 1024),
+            _ => None,
+        }
     }
 
     fn lwm_get_value(&self, src: &str, key: &str) -> u64 {
-        let mut value = String::new();
-
-        src.lines().for_each(|e| {
-            // If we're able to find a match
-            if e.starts_with(key) {
-                let second = e.split(':').nth(1).unwrap();
-                if second.contains("kB") {
-                    value.push_str(second.trim_end_matches("kB").trim());
-                } else {
-                    value.push_str(second.trim());
+        // Keys missing on older kernels, or lines that don't parse, count as zero
+        self.lwm_find_value(src, key)
+            .and_then(|e| self.lwm_parse_value(e))
+            .unwrap_or(0)
+    }
+
+    // Keys every meminfo has, without them the output would be meaningless
+    fn lwm_check_required(&self, src: &str) -> Result<(), String> {
+        ["MemTotal:", "MemFree:"].iter().try_for_each(|key| {
+            match self
+                .lwm_find_value(src, key)
+                .and_then(|e| self.lwm_parse_value(e))
+            {
+                Some(_) => Ok(()),
+                None => Err(format!("{MEMINFO_PATH}: no valid {key} line")),
+            }
+        })
+    }
+
+    // Most recent value of a journal field. journalctl lists the newest
+    // entries first and gets stopped as soon as one has the field
+    fn lwm_read_journal(&self, field: &str) -> Result<String, String> {
+        let mut child = process::Command::new("journalctl")
+            .args(["--reverse", "--output=export"])
+            .arg(format!("--output-fields={field}"))
+            .stdout(process::Stdio::piped())
+            .stderr(process::Stdio::null())
+            .spawn()
+            .map_err(|e| match e.kind() {
+                std::io::ErrorKind::NotFound => {
+                    "journalctl not found, --from-journal needs the systemd journal".to_string()
                 }
+                _ => format!("journalctl: {e}"),
+            })?;
+
+        let value = child
+            .stdout
+            .take()
+            .and_then(|e| self.lwm_journal_value(&mut BufReader::new(e), field));
+        let _ = child.kill();
+        let _ = child.wait();
+
+        value.ok_or(format!("journal has no {field} field"))
+    }
+
+    // First value of a field in journalctl's export format. Text values
+    // are NAME=value lines, values with newlines in them (like a meminfo
+    // snapshot) are a NAME line, a little-endian 64-bit length and the data
+    fn lwm_journal_value(&self, reader: &mut impl BufRead, field: &str) -> Option<String> {
+        loop {
+            let mut line = Vec::new();
+            if reader.read_until(b'\n', &mut line).ok()? == 0 {
+                return None;
             }
-        });
+            if line.pop() != Some(b'\n') {
+                return None;
+            }
+
+            // A blank line ends an entry
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(eq) = line.iter().position(|e| *e == b'=') {
+                if &line[..eq] == field.as_bytes() {
+                    return String::from_utf8(line[eq + 1..].to_vec()).ok();
+                }
+                continue;
+            }
+
+            let mut size = [0; 8];
+            reader.read_exact(&mut size).ok()?;
+            let mut data = vec![0; u64::from_le_bytes(size) as usize];
+            reader.read_exact(&mut data).ok()?;
+            reader.read_until(b'\n', &mut Vec::new()).ok()?;
+
+            if line == field.as_bytes() {
+                return String::from_utf8(data).ok();
+            }
+        }
+    }
+
+    #[inline]
+    fn lwm_has_key(&self, src: &str, key: &str) -> bool {
+        self.lwm_find_value(src, key).is_some()
+    }
+
+    // Per-node meminfo (/sys/devices/system/node/node*/meminfo) prefixes
+    // every line with "Node <n> ", drop it so the keys match
+    fn lwm_strip_node<'a>(&self, line: &'a str) -> &'a str {
+        line.strip_prefix("Node ")
+            .and_then(|e| e.split_once(' '))
+            .filter(|(node, _)| node.chars().all(|c| c.is_ascii_digit()))
+            .map_or(line, |(_, rest)| rest.trim_start())
+    }
+
+    fn lwm_attach_values(&mut self) -> Result<(), String> {
+        if let Some(field) = &self.from_journal {
+            let src = self.lwm_read_journal(field)?;
+            self.lwm_check_required(&src)
+                .map_err(|_| format!("journal field {field}: not a meminfo snapshot"))?;
+
+            self.lwm_parse(&src);
+            return Ok(());
+        }
+
+        let mut src = self.lwm_read_file()?;
+
+        // Reading while the kernel updates meminfo can rarely give a
+        // truncated line, so read it again once before giving up
+        if self.lwm_check_required(&src).is_err() {
+            src = self.lwm_read_file()?;
+            self.lwm_check_required(&src)?;
+        }
 
-        value.parse::<u64>().unwrap()
+        self.lwm_parse(&src);
+        Ok(())
     }
 
-    fn lwm_attach_values(&mut self) {
-        let src = self.lwm_read_file();
+    fn lwm_parse(&mut self, src: &str) {
+        self.mem_total = self.lwm_get_value(src, "MemTotal:");
+        self.mem_free = self.lwm_get_value(src, "MemFree:");
+        self.mem_avail = self.lwm_get_value(src, "MemAvailable:");
+        self.buffers = self.lwm_get_value(src, "Buffers:");
+        self.cached = self.lwm_get_value(src, "Cached:");
+        self.swap_cached = self.lwm_get_value(src, "SwapCached:");
+        self.swap_free = self.lwm_get_value(src, "SwapFree:");
+        self.swap_total = self.lwm_get_value(src, "SwapTotal:");
+        self.swap_used = self.swap_total.saturating_sub(self.swap_free);
+        self.zswap = self.lwm_get_value(src, "Zswap:");
+        self.zswapped = self.lwm_get_value(src, "Zswapped:");
+        self.shmem = self.lwm_get_value(src, "Shmem:");
+        self.s_reclaimable = self.lwm_get_value(src, "SReclaimable:");
+        self.mem_used = match self.used_mode {
+            LwmUsed::Available => self.mem_total.saturating_sub(self.mem_avail),
+            LwmUsed::Free => self.mem_total.saturating_sub(self.mem_free),
+            LwmUsed::Cache => self
+                .mem_total
+                .saturating_sub(self.mem_free + self.buffers + self.cached + self.s_reclaimable),
+        };
+        self.k_reclaimable = self.lwm_get_value(src, "KReclaimable:");
+        self.has_kreclaimable = self.lwm_has_key(src, "KReclaimable:");
+        self.anon_pages = self.lwm_get_value(src, "AnonPages:");
+        self.slab = self.lwm_get_value(src, "Slab:");
+        self.kernel_stack = self.lwm_get_value(src, "KernelStack:");
+        self.page_tables = self.lwm_get_value(src, "PageTables:");
+        self.hugetlb = self.lwm_get_value(src, "Hugetlb:");
+        self.dirty = self.lwm_get_value(src, "Dirty:");
+        self.writeback = self.lwm_get_value(src, "Writeback:");
+        self.vmalloc_total = self.lwm_get_value(src, "VmallocTotal:");
+        self.vmalloc_used = self.lwm_get_value(src, "VmallocUsed:");
+        self.vmalloc_chunk = self.lwm_get_value(src, "VmallocChunk:");
+        self.has_vmalloc = self.lwm_has_key(src, "VmallocTotal:");
+        self.anon_huge_pages = self.lwm_get_value(src, "AnonHugePages:");
+        self.shmem_huge_pages = self.lwm_get_value(src, "ShmemHugePages:");
+        self.shmem_pmd_mapped = self.lwm_get_value(src, "ShmemPmdMapped:");
+        self.has_thp = ["AnonHugePages:", "ShmemHugePages:", "ShmemPmdMapped:"]
+            .iter()
+            .any(|e| self.lwm_has_key(src, e));
+        self.percpu = self.lwm_get_value(src, "Percpu:");
+        self.has_percpu = self.lwm_has_key(src, "Percpu:");
+        self.cma_total = self.lwm_get_value(src, "CmaTotal:");
+        self.cma_free = self.lwm_get_value(src, "CmaFree:");
+        self.has_cma = self.lwm_has_key(src, "CmaTotal:");
+    }
+
+    // Value of a field by its identifier (the same ones used for labels)
+    fn lwm_field(&self, id: &str) -> Option<u64> {
+        let value = match id {
+            "mem_total" => self.mem_total,
+            "mem_free" => self.mem_free,
+            "mem_avail" => self.mem_avail,
+            "mem_used" => self.mem_used,
+            "mem_reclaimable" => self.lwm_reclaimable(),
+            "buffers" => self.buffers,
+            "cached" => self.cached,
+            "swap_cached" => self.swap_cached,
+            "swap_total" => self.swap_total,
+            "swap_free" => self.swap_free,
+            "swap_used" => self.swap_used,
+            "zswap" => self.zswap,
+            "zswapped" => self.zswapped,
+            "shmem" => self.shmem,
+            "s_reclaimable" => self.s_reclaimable,
+            "k_reclaimable" => self.k_reclaimable,
+            "anon_pages" => self.anon_pages,
+            "slab" => self.slab,
+            "kernel_stack" => self.kernel_stack,
+            "page_tables" => self.page_tables,
+            "hugetlb" => self.hugetlb,
+            "dirty" => self.dirty,
+            "writeback" => self.writeback,
+            "vmalloc_total" => self.vmalloc_total,
+            "vmalloc_used" => self.vmalloc_used,
+            "vmalloc_chunk" => self.vmalloc_chunk,
+            "anon_huge_pages" => self.anon_huge_pages,
+            "shmem_huge_pages" => self.shmem_huge_pages,
+            "shmem_pmd_mapped" => self.shmem_pmd_mapped,
+            "percpu" => self.percpu,
+            "cma_total" => self.cma_total,
+            "cma_free" => self.cma_free,
+            _ => return None,
+        };
 
-        self.mem_total = self.lwm_get_value(&src, "MemTotal:");
-        self.mem_free = self.lwm_get_value(&src, "MemFree:");
-        self.mem_avail = self.lwm_get_value(&src, "MemAvailable:");
-        self.mem_used = self.mem_total - self.mem_avail;
-        self.buffers = self.lwm_get_value(&src, "Buffers:");
-        self.cached = self.lwm_get_value(&src, "Cached:");
-        self.swap_cached = self.lwm_get_value(&src, "SwapCached:");
-        self.swap_free = self.lwm_get_value(&src, "SwapFree:");
-        self.swap_total = self.lwm_get_value(&src, "SwapTotal:");
-        self.swap_used = self.swap_total - self.swap_free;
-        self.zswap = self.lwm_get_value(&src, "Zswap:");
-        self.zswapped = self.lwm_get_value(&src, "Zswapped:");
-        self.shmem = self.lwm_get_value(&src, "Shmem:");
-        self.s_reclaimable = self.lwm_get_value(&src, "SReclaimable:");
+        Some(value)
     }
 
     // Taken from: https://git.sr.ht/~nkeor/human_bytes/tree/main/item/src/lib.rs
-    fn lwm_conv_to_hbytes(&self, size: f64, binary: bool) -> String {
+    fn lwm_conv_to_hbytes(&self, size: f64, binary: bool, round: bool, short: bool) -> String {
         if size <= 0.0 {
             return "0B".to_string();
         }
 
         // If binary use 1024, and if not (decimal) use 1000 as the unit
-        let unit = if binary { 1024.0 } else { 1000.0 } as f64;
-        let base = size.log10() / unit.log10();
+        let unit: f64 = if binary { 1024.0 } else { 1000.0 };
+        // Anything past petabytes stays in petabytes, as there's no larger suffix
+        let base = (size.log10() / unit.log10()).floor().clamp(0.0, 5.0);
+        let value = size / unit.powf(base);
         let mut buffer = ryu::Buffer::new();
-        let result = buffer
+        let result = if round {
             // Source for this hack: https://stackoverflow.com/a/28656825
-            .format((unit.powf(base - base.floor()) * 10.0).round() / 10.0);
+            buffer.format((value * 10.0).round() / 10.0)
+        } else {
+            // Full precision, whole numbers without the trailing .0
+            let exact = buffer.format(value);
+            exact.strip_suffix(".0").unwrap_or(exact)
+        };
 
         // Add suffix
-        if binary {
+        if short {
+            const SUFFIX: [&str; 6] = ["B", "K", "M", "G", "T", "P"];
+            [result, SUFFIX[base as usize]].join("")
+        } else if binary {
             const SUFFIX: [&str; 6] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB"];
-            [result, SUFFIX[base.floor() as usize]].join("")
+            [result, SUFFIX[base as usize]].join("")
         } else {
             const SUFFIX: [&str; 6] = ["B", "KB", "MB", "GB", "TB", "PB"];
-            [result, SUFFIX[base.floor() as usize]].join("")
-        }
-    }
-
-    fn lwm_print_all(&self, is_binary: bool, is_frndly: bool, is_color: bool) {
-        let unit = if is_binary { 1024.0 } else { 1000.0 };
-
-        if is_frndly {
-            if is_color {
-                let output = format!(
-                    "======================\n\
-                     | Memory Information |\n\
-                     ======================\n\
-                     * {WHITE_COLOR}Total Memory{END_COLOR}: {}\n\
-                     * {WHITE_COLOR}Free Memory{END_COLOR}: {}\n\
-                     * {WHITE_COLOR}Avail Memory{END_COLOR}: {}\n\
-                     * {WHITE_COLOR}Used Memory{END_COLOR}: {}\n\
-                     * {WHITE_COLOR}Buffered{END_COLOR}: {}\n\
-                     * {WHITE_COLOR}Total Swap{END_COLOR}: {}\n\
-                     * {WHITE_COLOR}Free Swap{END_COLOR}: {}\n\
-                     * {WHITE_COLOR}Cached Swap{END_COLOR}: {}\n\
-                     * {WHITE_COLOR}Used Swap{END_COLOR}: {}\n\
-                     * {WHITE_COLOR}Total ZSwap{END_COLOR}: {}\n\
-                     * {WHITE_COLOR}Commit ZSwap{END_COLOR}: {}\n\
-                     * {WHITE_COLOR}Shared Memory{END_COLOR}: {}",
-                    self.lwm_conv_to_hbytes(to_bytes!(self.mem_total, unit) as f64, is_binary),
-                    self.lwm_conv_to_hbytes(to_bytes!(self.mem_free, unit) as f64, is_binary),
-                    self.lwm_conv_to_hbytes(to_bytes!(self.mem_avail, unit) as f64, is_binary),
-                    self.lwm_conv_to_hbytes(to_bytes!(self.mem_used, unit) as f64, is_binary),
-                    self.lwm_conv_to_hbytes(to_bytes!(self.buffers, unit) as f64, is_binary),
-                    self.lwm_conv_to_hbytes(to_bytes!(self.swap_total, unit) as f64, is_binary),
-                    self.lwm_conv_to_hbytes(to_bytes!(self.swap_free, unit) as f64, is_binary),
-                    self.lwm_conv_to_hbytes(to_bytes!(self.swap_cached, unit) as f64, is_binary),
-                    self.lwm_conv_to_hbytes(to_bytes!(self.swap_used, unit) as f64, is_binary),
-                    self.lwm_conv_to_hbytes(to_bytes!(self.zswap, unit) as f64, is_binary),
-                    self.lwm_conv_to_hbytes(to_bytes!(self.zswapped, unit) as f64, is_binary),
-                    self.lwm_conv_to_hbytes(to_bytes!(self.shmem, unit) as f64, is_binary)
-                );
-                println!("{}", output);
-            } else {
-                let output = format!(
-                    "======================\n\
-                     | Memory Information |\n\
-                     ======================\n\
-                     * Total Memory: {}\n\
-                     * Free Memory: {}\n\
-                     * Avail Memory: {}\n\
-                     * Used Memory: {}\n\
-                     * Buffered: {}\n\
-                     * Total Swap: {}\n\
-                     * Free Swap: {}\n\
-                     * Cached Swap: {}\n\
-                     * Used Swap: {}\n\
-                     * Total ZSwap: {}\n\
-                     * Commit ZSwap: {}\n\
-                     * Shared Memory: {}",
-                    self.lwm_conv_to_hbytes(to_bytes!(self.mem_total, unit) as f64, is_binary),
-                    self.lwm_conv_to_hbytes(to_bytes!(self.mem_free, unit) as f64, is_binary),
-                    self.lwm_conv_to_hbytes(to_bytes!(self.mem_avail, unit) as f64, is_binary),
-                    self.lwm_conv_to_hbytes(to_bytes!(self.mem_used, unit) as f64, is_binary),
-                    self.lwm_conv_to_hbytes(to_bytes!(self.buffers, unit) as f64, is_binary),
-                    self.lwm_conv_to_hbytes(to_bytes!(self.swap_total, unit) as f64, is_binary),
-                    self.lwm_conv_to_hbytes(to_bytes!(self.swap_free, unit) as f64, is_binary),
-                    self.lwm_conv_to_hbytes(to_bytes!(self.swap_cached, unit) as f64, is_binary),
-                    self.lwm_conv_to_hbytes(to_bytes!(self.swap_used, unit) as f64, is_binary),
-                    self.lwm_conv_to_hbytes(to_bytes!(self.zswap, unit) as f64, is_binary),
-                    self.lwm_conv_to_hbytes(to_bytes!(self.zswapped, unit) as f64, is_binary),
-                    self.lwm_conv_to_hbytes(to_bytes!(self.shmem, unit) as f64, is_binary)
+            [result, SUFFIX[base as usize]].join("")
+        }
+    }
+
+    // Rows of the main "Memory Information" section
+    fn lwm_mem_rows(&self, is_wide: bool) -> Vec<(&'static str, u64)> {
+        let mut rows = vec![
+            ("mem_total", self.mem_total),
+            ("mem_free", self.mem_free),
+            ("mem_avail", self.mem_avail),
+            ("mem_reclaimable", self.lwm_reclaimable()),
+            ("mem_used", self.mem_used),
+            ("buffers", self.buffers),
+            ("swap_total", self.swap_total),
+            ("swap_free", self.swap_free),
+            ("swap_cached", self.swap_cached),
+            ("swap_used", self.swap_used),
+            ("zswap", self.zswap),
+            ("zswapped", self.zswapped),
+            ("shmem", self.shmem),
+        ];
+
+        // Same split as `free -w`, where cache includes reclaimable slab
+        if is_wide {
+            let cached = self.cached + self.s_reclaimable;
+            rows.insert(6, ("cached", cached));
+            rows.insert(7, ("buff_cache", self.buffers + cached));
+        }
+
+        rows
+    }
+
+    fn lwm_render_all(&self, unit: LwmUnit, is_color: bool, is_wide: bool) -> String {
+        let title = self
+            .title
+            .as_deref()
+            .unwrap_or(self.lwm_label("title_memory"));
+        let mut rows: Vec<(&str, String)> = Vec::new();
+
+        self.lwm_mem_rows(is_wide).iter().for_each(|(id, value)| {
+            let mut value = self.lwm_fmt_shown(id, *value, unit);
+            if *id == "shmem" && self.shmem_detail && self.has_thp {
+                value += &format!(
+                    " ({} {})",
+                    self.lwm_fmt_size(self.shmem_huge_pages, unit),
+                    self.lwm_label("shmem_huge")
                 );
-                println!("{}", output);
             }
+            rows.push((id, value));
+
+            // The ratio goes right below the zswap sizes it's computed from
+            if *id == "zswapped" {
+                rows.push(("zswap_ratio", self.lwm_zswap_ratio()));
+            }
+        });
+        let mut out = self.lwm_banner(title);
+        if self.groups {
+            out += &self.lwm_render_groups(&rows, &self.lwm_render_rows(&rows, is_color));
+        } else {
+            out += &self.lwm_render_rows(&rows, is_color);
+        }
+
+        // tmpfs is what usually makes Shmem large, /dev/shm included
+        if self.shmem_detail {
+            out += &format!("{}\n", self.lwm_label("shmem_note"));
+        }
+
+        out
+    }
+
+    // Rendered rows (one line each) with a subheader wherever the group
+    // changes. Rows that aren't in the field table (zswap_ratio,
+    // buff_cache) stay in the group of the row above them
+    fn lwm_render_groups(&self, rows: &[(&str, String)], rendered: &str) -> String {
+        let mut group = None;
+
+        rows.iter()
+            .zip(rendered.lines())
+            .fold(String::new(), |mut out, ((id, _), line)| {
+                let current = lwm_field_group(id).or(group);
+                if let (Some(name), true) = (current, current != group) {
+                    if group.is_some() {
+                        out.push('\n');
+                    }
+                    out += &format!("-- {} --\n", self.lwm_label(&format!("group_{name}")));
+                    group = current;
+                }
+
+                out + line + "\n"
+            })
+    }
+
+    // A human size in bytes: 2GiB, 1.5GB, 512M or a plain byte count.
+    // Single letters (K, M...) are binary like the short suffixes
+    fn lwm_parse_size(&self, src: &str) -> Option<f64> {
+        let src = src.trim();
+        let split = src
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(src.len());
+        let (number, suffix) = src.split_at(split);
+        let number: f64 = number.parse().ok()?;
+
+        let (prefix, base) = match suffix.trim().to_ascii_lowercase().as_str() {
+            "" | "b" => return Some(number),
+            e if e.len() == 1 => (e.to_string(), 1024.0),
+            e if e.ends_with("ib") && e.len() == 3 => (e[..1].to_string(), 1024.0),
+            e if e.ends_with('b') && e.len() == 2 => (e[..1].to_string(), 1000.0),
+            _ => return None,
+        };
+        let power = ["k", "m", "g", "t", "p"]
+            .iter()
+            .position(|e| *e == prefix)? as i32
+            + 1;
+
+        Some(number * f64::powi(base, power))
+    }
+
+    // Check a "<field> <op> <size or percent>" condition, e.g. "mem_avail > 2GiB".
+    // Percentages are of the swap total for swap fields, of MemTotal otherwise.
+    // Ok(None) when it holds, Ok(Some(why)) when it doesn't
+    fn lwm_assert(&self, expr: &str) -> Result<Option<String>, String> {
+        let start = expr
+            .find(['<', '>', '='])
+            .ok_or(format!("{expr}: expected <field> <op> <value>"))?;
+        let (field, rest) = expr.split_at(start);
+        let field = field.trim();
+        let op = if rest[1..].starts_with('=') {
+            &rest[..2]
+        } else {
+            &rest[..1]
+        };
+        let limit = rest[op.len()..].trim();
+
+        let value = self
+            .lwm_field(field)
+            .ok_or(format!("{expr}: unknown field {field} (see --help-fields)"))?;
+        let (actual, limit, shown) = match limit.strip_suffix('%') {
+            Some(percent) => {
+                let total = if field.starts_with("swap_") {
+                    self.swap_total
+                } else {
+                    self.mem_total
+                };
+                let percent = percent
+                    .trim()
+                    .parse::<f64>()
+                    .map_err(|_| format!("{expr}: invalid percentage {limit}"))?;
+
+                (
+                    self.lwm_percent(value, total),
+                    percent,
+                    format!("{}%", self.lwm_fmt_percent(value, total, 1)),
+                )
+            }
+            None => {
+                let size = self
+                    .lwm_parse_size(limit)
+                    .ok_or(format!("{expr}: invalid size {limit}"))?;
+                let unit = LwmUnit::Friendly {
+                    binary: true,
+                    round: true,
+                    short: false,
+                };
+
+                (
+                    to_bytes!(value, TO_KiB),
+                    size,
+                    self.lwm_fmt_size(value, unit),
+                )
+            }
+        };
+
+        let holds = match op {
+            "<" => actual < limit,
+            "<=" => actual <= limit,
+            ">" => actual > limit,
+            ">=" => actual >= limit,
+            "==" => actual == limit,
+            _ => return Err(format!("{expr}: unknown operator {op}")),
+        };
+
+        Ok((!holds).then(|| format!("{field} is {shown}")))
+    }
+
+    // Memory that's in use but the kernel can take back on demand (mostly
+    // cache), which is why available is larger than free
+    fn lwm_reclaimable(&self) -> u64 {
+        self.mem_avail.saturating_sub(self.mem_free)
+    }
+
+    // How well zswap compresses: original size of the pages over their
+    // compressed size
+    fn lwm_zswap_ratio(&self) -> String {
+        if self.zswap == 0 {
+            return "n/a".to_string();
+        }
+
+        format!("{:.1}x", self.zswapped as f64 / self.zswap as f64)
+    }
+
+    // Percentage of total that's being used, 0 when there's no total
+    fn lwm_percent(&self, used: u64, total: u64) -> f64 {
+        if total == 0 {
+            return 0.0;
+        }
+
+        used as f64 * 100.0 / total as f64
+    }
+
+    // Percentage rounded down to a number of decimals, so it only reads
+    // 100 when used really is the total (99.6% is 99, not 100)
+    fn lwm_floor_percent(&self, used: u64, total: u64, decimals: i32) -> f64 {
+        let scale = 10f64.powi(decimals);
+        let percent = (self.lwm_percent(used, total) * scale).floor() / scale;
+
+        if used < total {
+            percent.min(100.0 - 1.0 / scale)
+        } else {
+            percent
+        }
+    }
+
+    // Displayed percentage, tiny but nonzero usage shows as <1 (or <0.1...)
+    fn lwm_fmt_percent(&self, used: u64, total: u64, decimals: i32) -> String {
+        let percent = self.lwm_floor_percent(used, total, decimals);
+
+        if used > 0 && total > 0 && percent == 0.0 {
+            format!("<{}", 1.0 / 10f64.powi(decimals))
+        } else {
+            format!("{:.*}", decimals as usize, percent)
+        }
+    }
+
+    // All of the main rows on one line, as field=value pairs
+    fn lwm_oneline(&self, unit: LwmUnit) -> String {
+        self.lwm_mem_rows(false)
+            .iter()
+            .map(|(id, value)| format!("{}={}", id, self.lwm_fmt_size(*value, unit)))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn lwm_hostname(&self) -> Result<String, String> {
+        fs::read_to_string(HOSTNAME_PATH)
+            .map(|e| e.trim().to_string())
+            .map_err(|e| format!("{HOSTNAME_PATH}: {e}"))
+    }
+
+    // The main rows as an InfluxDB line protocol point, integer bytes
+    // tagged with the hostname and timestamped in nanoseconds
+    fn lwm_influx(&self, timestamp: u128) -> Result<String, String> {
+        // Tag values escape commas, spaces and equal signs
+        let host = self
+            .lwm_hostname()?
+            .replace(',', "\\,")
+            .replace(' ', "\\ ")
+            .replace('=', "\\=");
+        let fields = self
+            .lwm_mem_rows(false)
+            .iter()
+            .map(|(id, value)| format!("{}={}i", id, value * 1024))
+            .collect::<Vec<_>>()
+            .join(",");
+
+        Ok(format!("lwm,host={host} {fields} {timestamp}\n"))
+    }
+
+    fn lwm_syslog(&self, unit: LwmUnit, facility: LwmFacility, tag: &str) -> Result<(), String> {
+        // Severity is always LOG_INFO (6)
+        let message = format!(
+            "<{}>{}[{}]: {}",
+            facility.lwm_code() as u32 * 8 + 6,
+            tag,
+            process::id(),
+            self.lwm_oneline(unit)
+        );
+
+        let socket = UnixDatagram::unbound().map_err(|e| e.to_string())?;
+        socket
+            .send_to(message.as_bytes(), SYSLOG_PATH)
+            .map_err(|e| format!("{SYSLOG_PATH}: {e}"))?;
+
+        Ok(())
+    }
+
+    fn lwm_render_bare(&self, unit: LwmUnit) -> String {
+        // Keep this order as it is, scripts index into it (see --bare)
+        const BARE_FIELDS: [&str; 12] = [
+            "mem_total",
+            "mem_free",
+            "mem_avail",
+            "mem_used",
+            "buffers",
+            "swap_total",
+            "swap_free",
+            "swap_cached",
+            "swap_used",
+            "zswap",
+            "zswapped",
+            "shmem",
+        ];
+
+        let values: Vec<String> = BARE_FIELDS
+            .iter()
+            .filter_map(|e| Some(self.lwm_fmt_value(e, self.lwm_field(e)?, unit)))
+            .collect();
+
+        format!("{}\n", values.join(" "))
+    }
+
+    fn lwm_print_bare(&self, unit: LwmUnit) {
+        self.lwm_write(&self.lwm_render_bare(unit));
+    }
+
+    fn lwm_render_help_fields(&self, is_color: bool) -> String {
+        let rows: Vec<[&str; 5]> = LWM_FIELDS
+            .iter()
+            .map(|(id, group, key, description)| {
+                [*id, *group, self.lwm_label(id), *key, *description]
+            })
+            .collect();
+
+        let header = ["Field", "Group", "Label", "Meminfo", "Description"];
+        let mut widths = header.map(|e| e.chars().count());
+        rows.iter().for_each(|row| {
+            row.iter()
+                .enumerate()
+                .for_each(|(i, e)| widths[i] = widths[i].max(e.chars().count()))
+        });
+
+        let line = |row: &[&str; 5]| {
+            row.iter()
+                .enumerate()
+                .map(|(i, e)| format!("{:<w$}", e, w = widths[i]))
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        };
+
+        let mut out = if is_color {
+            format!("{WHITE_COLOR}{}{END_COLOR}\n", line(&header))
+        } else {
+            format!("{}\n", line(&header))
+        };
+        rows.iter()
+            .for_each(|row| out += &format!("{}\n", line(row)));
+
+        out
+    }
+
+    fn lwm_print_help_fields(&self, is_color: bool) {
+        self.lwm_write(&self.lwm_render_help_fields(is_color));
+    }
+
+    // A field in each of the fixed units: what the unit's flag would
+    // print, and the value before it gets truncated
+    fn lwm_render_explain_unit(&self, id: &str, is_color: bool) -> Result<String, String> {
+        let value = self
+            .lwm_field(id)
+            .ok_or(format!("unknown field {id} (see --help-fields)"))?;
+
+        let mut buffer = ryu::Buffer::new();
+        let rows: Vec<[String; 4]> = LWM_UNITS
+            .iter()
+            .map(|(name, flag, size)| {
+                [
+                    name.to_string(),
+                    flag.to_string(),
+                    self.lwm_fmt_size(value, LwmUnit::Size(*size)),
+                    buffer.format(to_size!(value, *size)).to_string(),
+                ]
+            })
+            .collect();
+
+        let header = ["Unit", "Flag", "Printed", "Exact"].map(String::from);
+        let mut widths = header.clone().map(|e| e.chars().count());
+        rows.iter().for_each(|row| {
+            row.iter()
+                .enumerate()
+                .for_each(|(i, e)| widths[i] = widths[i].max(e.chars().count()))
+        });
+
+        // Units and flags to the left, numbers to the right
+        let line = |row: &[String; 4]| {
+            row.iter()
+                .enumerate()
+                .map(|(i, e)| match i {
+                    0 | 1 => format!("{:<w$}", e, w = widths[i]),
+                    _ => format!("{:>w$}", e, w = widths[i]),
+                })
+                .collect::<Vec<_>>()
+                .join("  ")
+        };
+
+        let mut out = format!("{} = {value} kB\n", self.lwm_label(id));
+        out += &if is_color {
+            format!("{WHITE_COLOR}{}{END_COLOR}\n", line(&header))
+        } else {
+            format!("{}\n", line(&header))
+        };
+        rows.iter()
+            .for_each(|row| out += &format!("{}\n", line(row)));
+
+        Ok(out)
+    }
+
+    fn lwm_read_swaps(&self) -> Vec<LwmSwap> {
+        let src = fs::read_to_string(SWAPS_PATH).unwrap_or_default();
+
+        // The first line is always the header, with no devices that's all there is
+        src.lines()
+            .skip(1)
+            .filter_map(|e| {
+                let mut cols = e.split_whitespace();
+
+                Some(LwmSwap {
+                    filename: cols.next()?.to_string(),
+                    kind: cols.next()?.to_string(),
+                    size: cols.next()?.parse().ok()?,
+                    used: cols.next()?.parse().ok()?,
+                    priority: cols.next()?.parse().ok()?,
+                })
+            })
+            .collect()
+    }
+
+    // Memory of a process, the values are in kilobytes just like meminfo's.
+    // None if there's no such process (anymore)
+    fn lwm_read_proc(&self, pid: u32) -> Result<Option<LwmProc>, String> {
+        let path = format!("/proc/{pid}/status");
+        let src = match fs::read_to_string(&path) {
+            Ok(src) => src,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => {
+                return Err(format!("{path}: permission denied"))
+            }
+            Err(e) => return Err(format!("{path}: {e}")),
+        };
+
+        // Kernel threads have no Vm* lines at all, they count as zero
+        Ok(Some(LwmProc {
+            pid,
+            name: self
+                .lwm_find_value(&src, "Name")
+                .map_or(String::new(), |e| e.trim().to_string()),
+            vm_rss: self.lwm_get_value(&src, "VmRSS:"),
+            vm_size: self.lwm_get_value(&src, "VmSize:"),
+            vm_swap: self.lwm_get_value(&src, "VmSwap:"),
+        }))
+    }
+
+    // Two processes next to each other, one that's gone shows up with no memory
+    fn lwm_render_compare(
+        &self,
+        first: u32,
+        second: u32,
+        unit: LwmUnit,
+        is_color: bool,
+    ) -> Result<String, String> {
+        let read = |pid: u32| -> Result<(String, [u64; 3]), String> {
+            Ok(match self.lwm_read_proc(pid)? {
+                Some(e) => (e.name, [e.vm_rss, e.vm_size, e.vm_swap]),
+                None => (self.lwm_label("proc_gone").to_string(), [0; 3]),
+            })
+        };
+        let (first_name, before) = read(first)?;
+        let (second_name, after) = read(second)?;
+
+        let title = format!(
+            "{} {first} ({first_name}) -> {second} ({second_name})",
+            self.lwm_label("title_process")
+        );
+        let rows: Vec<_> = ["vm_rss", "vm_size", "vm_swap"]
+            .iter()
+            .zip(before.iter().zip(after))
+            .map(|(id, (old, new))| (*id, *old, new))
+            .collect();
+
+        Ok(self.lwm_render_diff(&title, &rows, unit, is_color))
+    }
+
+    fn lwm_print_compare(
+        &self,
+        first: u32,
+        second: u32,
+        unit: LwmUnit,
+        is_color: bool,
+    ) -> Result<(), String> {
+        self.lwm_write(&self.lwm_render_compare(first, second, unit, is_color)?);
+        Ok(())
+    }
+
+    fn lwm_render_proc(&self, proc: &LwmProc, unit: LwmUnit, is_color: bool) -> String {
+        let title = format!(
+            "{} {} ({})",
+            self.lwm_label("title_process"),
+            proc.pid,
+            proc.name
+        );
+        let rows = [
+            ("vm_rss", proc.vm_rss),
+            ("vm_size", proc.vm_size),
+            ("vm_swap", proc.vm_swap),
+        ];
+
+        self.lwm_banner(&title)
+            + &rows
+                .iter()
+                .map(|(id, value)| {
+                    self.lwm_render_row(id, &self.lwm_fmt_shown(id, *value, unit), is_color)
+                })
+                .collect::<String>()
+    }
+
+    fn lwm_print_proc(&self, proc: &LwmProc, unit: LwmUnit, is_color: bool) {
+        self.lwm_write(&self.lwm_render_proc(proc, unit, is_color));
+    }
+
+    // A value as shown in a section, friendly values get the exact byte
+    // count next to them with --with-bytes
+    fn lwm_fmt_shown(&self, id: &str, value: u64, unit: LwmUnit) -> String {
+        // A few kilobytes of swap in use is noise, zero shown as zero is
+        // still telling. --with-bytes keeps the exact count
+        let noise = matches!(unit, LwmUnit::Friendly { .. })
+            && ["swap_used", "swap_cached"].contains(&id)
+            && value < self.swap_floor;
+        let shown = self.lwm_fmt_value(id, if noise { 0 } else { value }, unit);
+
+        match unit {
+            LwmUnit::Friendly { .. } if self.with_bytes && self.normalized.is_none() => {
+                format!("{shown} ({} B)", value * 1024)
+            }
+            _ => shown,
+        }
+    }
+
+    // A field's value, as a fraction of its total with --normalized (swap
+    // fields of the swap total, everything else of MemTotal)
+    fn lwm_fmt_value(&self, id: &str, value: u64, unit: LwmUnit) -> String {
+        let Some(precision) = self.normalized else {
+            return self.lwm_fmt_size(value, unit);
+        };
+
+        let total = if id.starts_with("swap_") {
+            self.swap_total
+        } else {
+            self.mem_total
+        };
+        let ratio = if total == 0 {
+            0.0
+        } else {
+            value as f64 / total as f64
+        };
+
+        format!("{:.*}", precision, ratio)
+    }
+
+    fn lwm_fmt_size(&self, value: u64, unit: LwmUnit) -> String {
+        match unit {
+            LwmUnit::Friendly {
+                binary,
+                round,
+                short,
+            } => {
+                // meminfo's kB are kibibytes, whichever base the output uses
+                self.lwm_conv_to_hbytes(to_bytes!(value, TO_KiB), binary, round, short)
+            }
+            LwmUnit::Size(size) => (to_size!(value, size) as u64).to_string(),
+        }
+    }
+
+    // All of the output goes out in a single write, so a terminal never
+    // shows half of it. A closed pipe (e.g. into head) isn't an error
+    fn lwm_write(&self, out: &str) {
+        let out = if self.clear {
+            format!("{CLEAR_SCREEN}{out}")
+        } else {
+            out.to_string()
+        };
+
+        let mut stdout = std::io::stdout().lock();
+        if let Err(e) = stdout
+            .write_all(out.as_bytes())
+            .and_then(|_| stdout.flush())
+        {
+            if e.kind() != std::io::ErrorKind::BrokenPipe {
+                eprintln!("lwm: stdout: {e}");
+                process::exit(1);
+            }
+        }
+    }
+
+    // Boxed title, as printed above every section
+    fn lwm_banner(&self, title: &str) -> String {
+        if self.compact {
+            return format!("{title}\n");
+        }
+
+        let line = "=".repeat(title.chars().count() + 4);
+        format!("{line}\n| {title} |\n{line}\n")
+    }
+
+    // Display label of a field or section, the identifier itself if there's none
+    fn lwm_label<'a>(&'a self, id: &'a str) -> &'a str {
+        self.labels.get(id).map(String::as_str).unwrap_or(id)
+    }
+
+    fn lwm_load_labels(&mut self, lang: LwmLang, path: Option<&str>) -> Result<(), String> {
+        // English first, so labels a language doesn't translate stay readable
+        [LwmLang::En, lang]
+            .iter()
+            .flat_map(|e| lwm_builtin_labels(*e))
+            .for_each(|(id, label)| {
+                self.labels.insert(id.to_string(), label.to_string());
+            });
+
+        // Only English has abbreviations, other languages keep their labels
+        if self.compact && matches!(lang, LwmLang::En) {
+            LABELS_COMPACT.iter().for_each(|(id, label)| {
+                self.labels.insert(id.to_string(), label.to_string());
+            });
+        }
+
+        if let Some(path) = path {
+            let src = fs::read_to_string(path).map_err(|e| format!("{path}: {e}"))?;
+            let labels = lwm_parse_labels(&src).map_err(|e| format!("{path}: {e}"))?;
+            self.labels.extend(labels);
+        }
+
+        Ok(())
+    }
+
+    // Signed difference between two values, e.g. +512.0MiB
+    fn lwm_fmt_delta(&self, before: u64, after: u64, unit: LwmUnit) -> String {
+        if after == before {
+            self.lwm_fmt_size(0, unit)
+        } else if after > before {
+            format!("+{}", self.lwm_fmt_size(after - before, unit))
+        } else {
+            format!("-{}", self.lwm_fmt_size(before - after, unit))
+        }
+    }
+
+    // Main rows added up over several meminfo files, files that can't be
+    // read or parsed are left out with a warning
+    fn lwm_render_sum(
+        &self,
+        paths: &[String],
+        unit: LwmUnit,
+        is_color: bool,
+    ) -> Result<String, String> {
+        let mut hosts = 0;
+        let mut rows: Vec<(&str, u64)> = Vec::new();
+
+        paths.iter().for_each(|path| {
+            let src = match fs::read_to_string(path) {
+                Ok(src) => src,
+                Err(e) => return eprintln!("lwm: warning: {path}: {e}, skipped"),
+            };
+            if self.lwm_check_required(&src).is_err() {
+                return eprintln!("lwm: warning: {path}: not a meminfo file, skipped");
+            }
+
+            let mut host = Lwm::new();
+            host.used_mode = self.used_mode;
+            host.lwm_parse(&src);
+            if rows.is_empty() {
+                rows = host.lwm_mem_rows(false);
+            } else {
+                rows.iter_mut()
+                    .zip(host.lwm_mem_rows(false))
+                    .for_each(|(total, (_, value))| total.1 += value);
+            }
+            hosts += 1;
+        });
+
+        if hosts == 0 {
+            return Err("none of the meminfo files could be read".to_string());
+        }
+
+        Ok(self.lwm_banner(self.lwm_label("title_sum"))
+            + &self.lwm_render_row("sum_hosts", &hosts.to_string(), is_color)
+            + &rows
+                .iter()
+                .map(|(id, value)| {
+                    self.lwm_render_row(id, &self.lwm_fmt_size(*value, unit), is_color)
+                })
+                .collect::<String>())
+    }
+
+    fn lwm_print_sum(&self, paths: &[String], unit: LwmUnit, is_color: bool) -> Result<(), String> {
+        self.lwm_write(&self.lwm_render_sum(paths, unit, is_color)?);
+        Ok(())
+    }
+
+    // Direction of a change, green when memory was freed and red when it
+    // was consumed (^ and v without colors). Nothing when it didn't change
+    fn lwm_delta_arrow(&self, id: &str, before: u64, after: u64, is_color: bool) -> String {
+        if before == after {
+            return String::new();
+        }
+
+        let (freed, consumed) = match self.palette {
+            LwmPalette::Default => (GREEN_COLOR, RED_COLOR),
+            LwmPalette::Colorblind => (BLUE_COLOR, ORANGE_COLOR),
+        };
+        // More free memory is memory that got freed, for everything else
+        // (used, cache, process sizes...) more is memory that got consumed
+        let is_up = after > before;
+        let is_freed = matches!(id, "mem_free" | "mem_avail" | "swap_free") == is_up;
+
+        match (is_color, is_up) {
+            (false, true) => " ^".to_string(),
+            (false, false) => " v".to_string(),
+            (true, _) => format!(
+                " {}{}{END_COLOR}",
+                if is_freed { freed } else { consumed },
+                if is_up { "↑" } else { "↓" }
+            ),
+        }
+    }
+
+    // The main section of every meminfo file, in filename order and titled
+    // with the filename. Files that can't be read or parsed are skipped
+    fn lwm_render_batch(
+        &mut self,
+        paths: &[String],
+        unit: LwmUnit,
+        is_color: bool,
+        is_wide: bool,
+    ) -> Result<String, String> {
+        let mut paths = paths.to_vec();
+        paths.sort();
+
+        let title = self.title.take();
+        let mut out = String::new();
+        paths.iter().for_each(|path| {
+            let src = match fs::read_to_string(path) {
+                Ok(src) => src,
+                Err(e) => return eprintln!("lwm: warning: {path}: {e}, skipped"),
+            };
+            if self.lwm_check_required(&src).is_err() {
+                return eprintln!("lwm: warning: {path}: not a meminfo file, skipped");
+            }
+
+            self.lwm_parse(&src);
+            self.title = Some(path.clone());
+            out += &self.lwm_render_all(unit, is_color, is_wide);
+        });
+        self.title = title;
+
+        if out.is_empty() {
+            return Err("none of the meminfo files could be read".to_string());
+        }
+
+        Ok(out)
+    }
+
+    // Main rows of `before` next to ours
+    fn lwm_delta_rows(&self, before: &Lwm) -> Vec<(&'static str, u64, u64)> {
+        before
+            .lwm_mem_rows(false)
+            .iter()
+            .zip(self.lwm_mem_rows(false))
+            .map(|((id, old), (_, new))| (*id, *old, new))
+            .collect()
+    }
+
+    // Rows of (id, old, new) values, with the change in between
+    fn lwm_render_diff(
+        &self,
+        title: &str,
+        rows: &[(&str, u64, u64)],
+        unit: LwmUnit,
+        is_color: bool,
+    ) -> String {
+        let mut out = self.lwm_banner(title);
+
+        rows.iter().for_each(|(id, old, new)| {
+            let values = format!(
+                "{} -> {} ({}{})",
+                self.lwm_fmt_size(*old, unit),
+                self.lwm_fmt_size(*new, unit),
+                self.lwm_fmt_delta(*old, *new, unit),
+                self.lwm_delta_arrow(id, *old, *new, is_color)
+            );
+
+            out += &if is_color {
+                format!(
+                    "* {WHITE_COLOR}{}{END_COLOR}: {}\n",
+                    self.lwm_link_label(id, self.lwm_label(id)),
+                    values
+                )
+            } else {
+                format!("* {}: {}\n", self.lwm_label(id), values)
+            };
+        });
+
+        out
+    }
+
+    fn lwm_print_diff(
+        &self,
+        title: &str,
+        rows: &[(&str, u64, u64)],
+        unit: LwmUnit,
+        is_color: bool,
+    ) {
+        self.lwm_write(&self.lwm_render_diff(title, rows, unit, is_color));
+    }
+
+    fn lwm_render_section(
+        &self,
+        title: &str,
+        rows: &[(&str, u64)],
+        unit: LwmUnit,
+        is_color: bool,
+    ) -> String {
+        let rows: Vec<(&str, String)> = rows
+            .iter()
+            .map(|(id, value)| (*id, self.lwm_fmt_shown(id, *value, unit)))
+            .collect();
+
+        self.lwm_banner(self.lwm_label(title)) + &self.lwm_render_rows(&rows, is_color)
+    }
+
+    // Rows of a section, aligned in columns with --auto-unit: labels, then
+    // the numbers lined up on the right and their unit suffix
+    fn lwm_render_rows(&self, rows: &[(&str, String)], is_color: bool) -> String {
+        if !self.auto_unit {
+            return rows
+                .iter()
+                .map(|(id, value)| self.lwm_render_row(id, value, is_color))
+                .collect();
+        }
+
+        let cells: Vec<(&str, &str, &str)> = rows
+            .iter()
+            .map(|(id, value)| {
+                let split = value
+                    .find(|c: char| !c.is_ascii_digit() && c != '.')
+                    .unwrap_or(value.len());
+                // Values that don't start with a number (n/a) go in the number column
+                let (number, suffix) = match value.split_at(split) {
+                    ("", _) => (value.as_str(), ""),
+                    e => e,
+                };
+                (self.lwm_label(id), number, suffix)
+            })
+            .collect();
+        let label_width = cells.iter().map(|e| e.0.chars().count()).max().unwrap_or(0);
+        let number_width = cells.iter().map(|e| e.1.len()).max().unwrap_or(0);
+
+        rows.iter()
+            .zip(cells)
+            .map(|((id, _), (label, number, suffix))| {
+                // Padded outside of the link, so the spaces aren't clickable
+                let pad = " ".repeat(label_width - label.chars().count());
+                let value = format!("{:>w$} {}", number, suffix, w = number_width);
+                let value = value.trim_end();
+                let label = if is_color {
+                    self.lwm_link_label(id, label) + &pad
+                } else {
+                    format!("{label}{pad}")
+                };
+
+                match (is_color, self.lwm_value_color(id)) {
+                    (false, _) => format!("* {label}  {value}\n"),
+                    (true, None) => format!("* {WHITE_COLOR}{label}{END_COLOR}  {value}\n"),
+                    (true, Some(color)) => {
+                        format!("* {WHITE_COLOR}{label}{END_COLOR}  {color}{value}{END_COLOR}\n")
+                    }
+                }
+            })
+            .collect()
+    }
+
+    // A single "* Label: value" line
+    fn lwm_render_row(&self, id: &str, value: &str, is_color: bool) -> String {
+        if is_color {
+            let value = match self.lwm_value_color(id) {
+                Some(color) => format!("{color}{}{END_COLOR}", value),
+                None => value.to_string(),
+            };
+
+            format!(
+                "* {WHITE_COLOR}{}{END_COLOR}: {}\n",
+                self.lwm_link_label(id, self.lwm_label(id)),
+                value
+            )
+        } else {
+            format!("* {}: {}\n", self.lwm_label(id), value)
+        }
+    }
+
+    // A colored row's label, an OSC 8 link with --links for fields that
+    // come from meminfo
+    fn lwm_link_label(&self, id: &str, label: &str) -> String {
+        if self.links && lwm_field_group(id).is_some() {
+            format!("\x1b]8;;{MEMINFO_DOC_URL}\x1b\\{label}\x1b]8;;\x1b\\")
+        } else {
+            label.to_string()
+        }
+    }
+
+    // Color of a field's value with --color-values, memory that can still
+    // be handed out is green (blue) and memory in use is yellow (orange)
+    fn lwm_value_color(&self, id: &str) -> Option<&'static str> {
+        if !self.color_values {
+            return None;
+        }
+
+        let (free, used) = match self.palette {
+            LwmPalette::Default => (GREEN_COLOR, YELLOW_COLOR),
+            LwmPalette::Colorblind => (BLUE_COLOR, ORANGE_COLOR),
+        };
+
+        match id {
+            "mem_free" | "mem_avail" | "swap_free" => Some(free),
+            "mem_used" | "swap_used" => Some(used),
+            _ => None,
+        }
+    }
+
+    // Sections that only show up when the kernel reports them
+    fn lwm_render_sections(&self, unit: LwmUnit, is_color: bool) -> String {
+        let mut kernel = vec![
+            ("slab", self.slab),
+            ("s_reclaimable", self.s_reclaimable),
+            ("kernel_stack", self.kernel_stack),
+            ("page_tables", self.page_tables),
+        ];
+        if self.has_kreclaimable {
+            kernel.insert(2, ("k_reclaimable", self.k_reclaimable));
+        }
+        if self.has_percpu {
+            kernel.push(("percpu", self.percpu));
+        }
+        let mut out = self.lwm_render_section("title_kernel", &kernel, unit, is_color);
+
+        if self.has_vmalloc {
+            out += &self.lwm_render_section(
+                "title_vmalloc",
+                &[
+                    ("vmalloc_total", self.vmalloc_total),
+                    ("vmalloc_used", self.vmalloc_used),
+                    ("vmalloc_chunk", self.vmalloc_chunk),
+                ],
+                unit,
+                is_color,
+            );
+        }
+
+        if self.has_thp {
+            out += &self.lwm_render_section(
+                "title_thp",
+                &[
+                    ("anon_huge_pages", self.anon_huge_pages),
+                    ("shmem_huge_pages", self.shmem_huge_pages),
+                    ("shmem_pmd_mapped", self.shmem_pmd_mapped),
+                ],
+                unit,
+                is_color,
+            );
+        }
+
+        // Reserved at boot, this is where the RAM missing from MemTotal
+        // on a Raspberry Pi goes
+        if self.has_cma {
+            out += &self.lwm_render_section(
+                "title_cma",
+                &[("cma_total", self.cma_total), ("cma_free", self.cma_free)],
+                unit,
+                is_color,
+            );
+        }
+
+        out
+    }
+
+    // Same layout as procps free: 8 wide row names, 11 wide right-aligned columns
+    fn lwm_render_free_compat(&self, unit: LwmUnit) -> String {
+        let header = ["total", "used", "free", "shared", "buff/cache", "available"];
+        let mem = [
+            self.mem_total,
+            self.mem_used,
+            self.mem_free,
+            self.shmem,
+            self.buffers + self.cached + self.s_reclaimable,
+            self.mem_avail,
+        ];
+        let swap = [self.swap_total, self.swap_used, self.swap_free];
+
+        let line = |name: &str, cells: Vec<String>| {
+            let cells: String = cells.iter().map(|e| format!(" {:>11}", e)).collect();
+            format!("{:<8}{}", name, cells).trim_end().to_string() + "\n"
+        };
+
+        line("", header.map(String::from).to_vec())
+            + &line(
+                "Mem:",
+                mem.iter().map(|e| self.lwm_fmt_size(*e, unit)).collect(),
+            )
+            + &line(
+                "Swap:",
+                swap.iter().map(|e| self.lwm_fmt_size(*e, unit)).collect(),
+            )
+    }
+
+    fn lwm_print_free_compat(&self, unit: LwmUnit) {
+        self.lwm_write(&self.lwm_render_free_compat(unit));
+    }
+
+    // Current and maximum ARC size (in kilobytes), None without ZFS
+    fn lwm_read_arcstats(&self) -> Option<(u64, u64)> {
+        let src = fs::read_to_string(ARCSTATS_PATH).ok()?;
+        let stat = |key: &str| {
+            src.lines()
+                .map(|e| e.split_whitespace().collect::<Vec<_>>())
+                .find(|e| e.len() == 3 && e[0] == key)
+                .and_then(|e| e[2].parse::<u64>().ok())
+        };
+
+        // arcstats counts in bytes, not kilobytes like meminfo
+        Some((stat("size")? / 1024, stat("c_max")? / 1024))
+    }
+
+    // Width of the terminal stdout is on, None when it's not a terminal
+    // or doesn't say (0). COLUMNS is rarely exported, so ask stty if it isn't
+    fn lwm_term_width(&self) -> Option<usize> {
+        if !std::io::stdout().is_terminal() {
+            return None;
+        }
+
+        if let Some(width) = std::env::var("COLUMNS")
+            .ok()
+            .and_then(|e| e.parse().ok())
+            .filter(|e| *e > 0)
+        {
+            return Some(width);
+        }
+
+        let tty = fs::File::open("/dev/tty").ok()?;
+        let output = process::Command::new("stty")
+            .arg("size")
+            .stdin(tty)
+            .output()
+            .ok()?;
+        String::from_utf8(output.stdout)
+            .ok()?
+            .split_whitespace()
+            .nth(1)?
+            .parse()
+            .ok()
+            .filter(|e| *e > 0)
+    }
+
+    // Size of a memory block (in kilobytes) and how many blocks sysfs
+    // lists and how many of them are online. None on systems without
+    // memory hotplug support
+    fn lwm_read_memory_blocks(&self) -> Option<(u64, u64, u64)> {
+        let size = fs::read_to_string(format!("{MEMORY_BLOCKS_PATH}/block_size_bytes")).ok()?;
+        let size = u64::from_str_radix(size.trim(), 16).ok()?;
+        let blocks: Vec<_> = fs::read_dir(MEMORY_BLOCKS_PATH)
+            .ok()?
+            .filter_map(|e| e.ok())
+            .filter(|e| {
+                e.file_name()
+                    .to_str()
+                    .and_then(|e| e.strip_prefix("memory"))
+                    .is_some_and(|e| !e.is_empty() && e.chars().all(|c| c.is_ascii_digit()))
+            })
+            .collect();
+
+        // Blocks that can't be taken offline may have no online file at all
+        let online = blocks
+            .iter()
+            .filter(|e| {
+                fs::read_to_string(e.path().join("online")).map_or(true, |e| e.trim() != "0")
+            })
+            .count();
+
+        (!blocks.is_empty()).then_some((size / 1024, blocks.len() as u64, online as u64))
+    }
+
+    // Installed RAM, and whether it's only an estimate: without memory
+    // blocks it's MemTotal rounded up to a power of two GiB. Offline
+    // blocks are installed too
+    fn lwm_installed(&self) -> (u64, bool) {
+        match self.lwm_read_memory_blocks() {
+            Some((size, blocks, _)) => (size * blocks, false),
+            None => {
+                let gib = 1024 * 1024;
+                (self.mem_total.div_ceil(gib).next_power_of_two() * gib, true)
+            }
+        }
+    }
+
+    // The kernel's MemAvailable against the estimate from before it
+    // existed. MemAvailable leaves out the part of the cache that can't
+    // be dropped (shmem, for one) and keeps the low watermarks free
+    fn lwm_render_both_avail(&self, unit: LwmUnit, is_color: bool) -> String {
+        // Same reclaimable slab the kernel counts towards MemAvailable
+        let reclaimable = if self.has_kreclaimable {
+            self.k_reclaimable
+        } else {
+            self.s_reclaimable
+        };
+        let classic = self.mem_free + self.buffers + self.cached + reclaimable;
+
+        self.lwm_render_section(
+            "title_avail",
+            &[("avail_kernel", self.mem_avail), ("avail_classic", classic)],
+            unit,
+            is_color,
+        ) + &self.lwm_render_row(
+            "avail_diff",
+            &self.lwm_fmt_delta(self.mem_avail, classic, unit),
+            is_color,
+        ) + &format!("{}\n", self.lwm_label("avail_note"))
+    }
+
+    fn lwm_render_hardware(&self, unit: LwmUnit, is_color: bool) -> String {
+        let (installed, is_estimate) = self.lwm_installed();
+        let prefix = if is_estimate { "~" } else { "" };
+
+        let mut out = self.lwm_banner(self.lwm_label("title_hardware"))
+            + &self.lwm_render_row(
+                "hw_installed",
+                &format!("{prefix}{}", self.lwm_fmt_size(installed, unit)),
+                is_color,
+            )
+            + &self.lwm_render_row(
+                "hw_usable",
+                &self.lwm_fmt_size(self.mem_total, unit),
+                is_color,
+            )
+            + &self.lwm_render_row(
+                "hw_reserved",
+                &format!(
+                    "{prefix}{}",
+                    self.lwm_fmt_size(installed.saturating_sub(self.mem_total), unit)
+                ),
+                is_color,
+            );
+
+        if is_estimate {
+            out += &format!("{}\n", self.lwm_label("hw_estimate"));
+        }
+
+        out
+    }
+
+    // System RAM, reserved ranges and the kernel image (code, rodata, data,
+    // bss) from an iomem listing, in kilobytes. Without root the kernel
+    // lists every range as 0-0 instead of refusing the read
+    fn lwm_parse_iomem(&self, src: &str) -> Result<(u64, u64, u64), String> {
+        let mut ram = 0;
+        let mut reserved = 0;
+        let mut kernel = 0;
+        let mut is_hidden = true;
+
+        src.lines().for_each(|line| {
+            let Some((range, name)) = line.split_once(" : ") else {
+                return;
+            };
+            let Some((start, end)) = range.trim().split_once('-').and_then(|(s, e)| {
+                Some((
+                    u64::from_str_radix(s, 16).ok()?,
+                    u64::from_str_radix(e, 16).ok()?,
+                ))
+            }) else {
+                return;
+            };
+            if end == 0 {
+                return;
+            }
+            is_hidden = false;
+
+            // Kernel image ranges are nested in System RAM, the rest are
+            // top-level
+            let size = (end - start + 1) / 1024;
+            let is_nested = line.starts_with(' ');
+            match (is_nested, name.trim()) {
+                (false, "System RAM") => ram += size,
+                (false, e) if e.eq_ignore_ascii_case("reserved") => reserved += size,
+                (true, e) if e.starts_with("Kernel ") => kernel += size,
+                _ => {}
+            }
+        });
+
+        if is_hidden {
+            return Err("addresses are hidden without root".to_string());
+        }
+
+        Ok((ram, reserved, kernel))
+    }
+
+    // What iomem says about memory the OS doesn't get, or a note on why
+    // it can't be read
+    fn lwm_render_iomem(
+        &self,
+        src: Result<String, String>,
+        unit: LwmUnit,
+        is_color: bool,
+    ) -> String {
+        let (ram, reserved, kernel) = match src.and_then(|e| self.lwm_parse_iomem(&e)) {
+            Ok(e) => e,
+            Err(e) => {
+                return self.lwm_banner(self.lwm_label("title_iomem"))
+                    + &format!("{}: {e}\n", self.lwm_label("iomem_unavailable"));
+            }
+        };
+
+        self.lwm_render_section(
+            "title_iomem",
+            &[
+                ("iomem_ram", ram),
+                ("iomem_not_total", ram.saturating_sub(self.mem_total)),
+                ("iomem_kernel", kernel),
+                ("iomem_reserved", reserved),
+            ],
+            unit,
+            is_color,
+        ) + &format!("{}\n", self.lwm_label("iomem_note"))
+    }
+
+    // Online and offline memory blocks, nothing without memory hotplug
+    fn lwm_render_hotplug(&self, unit: LwmUnit, is_color: bool) -> String {
+        let Some((size, blocks, online)) = self.lwm_read_memory_blocks() else {
+            return String::new();
+        };
+
+        self.lwm_banner(self.lwm_label("title_hotplug"))
+            + &self.lwm_render_row("hp_block_size", &self.lwm_fmt_size(size, unit), is_color)
+            + &self.lwm_render_row("hp_online", &online.to_string(), is_color)
+            + &self.lwm_render_row("hp_offline", &(blocks - online).to_string(), is_color)
+            + &self.lwm_render_row(
+                "hp_offline_size",
+                &self.lwm_fmt_size(size * (blocks - online), unit),
+                is_color,
+            )
+    }
+
+    // One "Status: ..." line: critical, warning or ok, with the condition
+    // that decided it
+    fn lwm_render_verdict(&self, is_color: bool) -> String {
+        let avail = self.lwm_percent(self.mem_avail, self.mem_total);
+        let swap = self.lwm_percent(self.swap_used, self.swap_total);
+
+        let (level, reason) = if avail < VERDICT_AVAIL_CRIT {
+            ("verdict_crit", Some("verdict_oom"))
+        } else if swap > VERDICT_SWAP_CRIT {
+            ("verdict_crit", Some("verdict_swap_heavy"))
+        } else if avail < VERDICT_AVAIL_WARN {
+            ("verdict_warn", Some("verdict_low"))
+        } else if swap > VERDICT_SWAP_WARN {
+            ("verdict_warn", Some("verdict_swap"))
         } else {
-            let output = format!(
-                "======================\n\
-                 | Memory Information |\n\
-                 ======================\n\
-                 * {WHITE_COLOR}Total Memory{END_COLOR}: {}\n\
-                 * {WHITE_COLOR}Free Memory{END_COLOR}: {}\n\
-                 * {WHITE_COLOR}Avail Memory{END_COLOR}: {}\n\
-                 * {WHITE_COLOR}Used Memory{END_COLOR}: {}\n\
-                 * {WHITE_COLOR}Buffered{END_COLOR}: {}\n\
-                 * {WHITE_COLOR}Total Swap{END_COLOR}: {}\n\
-                 * {WHITE_COLOR}Free Swap{END_COLOR}: {}\n\
-                 * {WHITE_COLOR}Cached Swap{END_COLOR}: {}\n\
-                 * {WHITE_COLOR}Used Swap{END_COLOR}: {}\n\
-                 * {WHITE_COLOR}Total ZSwap{END_COLOR}: {}\n\
-                 * {WHITE_COLOR}Commit ZSwap{END_COLOR}: {}\n\
-                 * {WHITE_COLOR}Shared Memory{END_COLOR}: {}",
-                to_bytes!(self.mem_total, 1024.0) as u64,
-                to_bytes!(self.mem_free, 1024.0) as u64,
-                to_bytes!(self.mem_avail, 1024.0) as u64,
-                to_bytes!(self.mem_used, 1024.0) as u64,
-                to_bytes!(self.buffers, 1024.0) as u64,
-                to_bytes!(self.swap_total, 1024.0) as u64,
-                to_bytes!(self.swap_free, 1024.0) as u64,
-                to_bytes!(self.swap_cached, 1024.0) as u64,
-                to_bytes!(self.swap_used, 1024.0) as u64,
-                to_bytes!(self.zswap, 1024.0) as u64,
-                to_bytes!(self.zswapped, 1024.0) as u64,
-                to_bytes!(self.shmem, 1024.0) as u64
-            );
-            println!("{}", output);
+            ("verdict_ok", None)
+        };
+
+        let mut verdict = self.lwm_label(level).to_string();
+        if let Some(reason) = reason {
+            verdict += &format!(" ({})", self.lwm_label(reason));
         }
-    }
 
-    fn lwm_print_to_size(&self, size: f64, is_color: bool) {
         if is_color {
-            let output = format!(
-                "======================\n\
-                 | Memory Information |\n\
-                 ======================\n\
-                 * {WHITE_COLOR}Total Memory{END_COLOR}: {}\n\
-                 * {WHITE_COLOR}Free Memory{END_COLOR}: {}\n\
-                 * {WHITE_COLOR}Avail Memory{END_COLOR}: {}\n\
-                 * {WHITE_COLOR}Used Memory{END_COLOR}: {}\n\
-                 * {WHITE_COLOR}Buffered{END_COLOR}: {}\n\
-                 * {WHITE_COLOR}Total Swap{END_COLOR}: {}\n\
-                 * {WHITE_COLOR}Free Swap{END_COLOR}: {}\n\
-                 * {WHITE_COLOR}Cached Swap{END_COLOR}: {}\n\
-                 * {WHITE_COLOR}Used Swap{END_COLOR}: {}\n\
-                 * {WHITE_COLOR}Total ZSwap{END_COLOR}: {}\n\
-                 * {WHITE_COLOR}Commit ZSwap{END_COLOR}: {}\n\
-                 * {WHITE_COLOR}Shared Memory{END_COLOR}: {}",
-                to_size!(self.mem_total, size) as u64,
-                to_size!(self.mem_free, size) as u64,
-                to_size!(self.mem_avail, size) as u64,
-                to_size!(self.mem_used, size) as u64,
-                to_size!(self.buffers, size) as u64,
-                to_size!(self.swap_total, size) as u64,
-                to_size!(self.swap_free, size) as u64,
-                to_size!(self.swap_cached, size) as u64,
-                to_size!(self.swap_used, size) as u64,
-                to_size!(self.zswap, size) as u64,
-                to_size!(self.zswapped, size) as u64,
-                to_size!(self.shmem, size) as u64
-            );
-            println!("{}", output);
-        } else {
-            let output = format!(
-                "======================\n\
-                 | Memory Information |\n\
-                 ======================\n\
-                 * Total Memory: {}\n\
-                 * Free Memory: {}\n\
-                 * Avail Memory: {}\n\
-                 * Used Memory: {}\n\
-                 * Buffered: {}\n\
-                 * Total Swap: {}\n\
-                 * Free Swap: {}\n\
-                 * Cached Swap: {}\n\
-                 * Used Swap: {}\n\
-                 * Total ZSwap: {}\n\
-                 * Commit ZSwap: {}\n\
-                 * Shared Memory: {}",
-                to_size!(self.mem_total, size) as u64,
-                to_size!(self.mem_free, size) as u64,
-                to_size!(self.mem_avail, size) as u64,
-                to_size!(self.mem_used, size) as u64,
-                to_size!(self.buffers, size) as u64,
-                to_size!(self.swap_total, size) as u64,
-                to_size!(self.swap_free, size) as u64,
-                to_size!(self.swap_cached, size) as u64,
-                to_size!(self.swap_used, size) as u64,
-                to_size!(self.zswap, size) as u64,
-                to_size!(self.zswapped, size) as u64,
-                to_size!(self.shmem, size) as u64
-            );
-            println!("{}", output);
+            let color = match (self.palette, level) {
+                (LwmPalette::Default, "verdict_ok") => GREEN_COLOR,
+                (LwmPalette::Default, "verdict_warn") => YELLOW_COLOR,
+                (LwmPalette::Default, _) => RED_COLOR,
+                (LwmPalette::Colorblind, "verdict_ok") => BLUE_COLOR,
+                (LwmPalette::Colorblind, "verdict_warn") => ORANGE_COLOR,
+                (LwmPalette::Colorblind, _) => MAGENTA_COLOR,
+            };
+            verdict = format!("{color}{verdict}{END_COLOR}");
+        }
+
+        format!("{}: {}\n", self.lwm_label("verdict"), verdict)
+    }
+
+    // 1, 5 and 15 minute load averages, n/a when they can't be read
+    fn lwm_render_loadavg(&self, is_color: bool) -> String {
+        let src = fs::read_to_string(LOADAVG_PATH).unwrap_or_default();
+        let mut loads = src.split_whitespace();
+
+        ["load_1", "load_5", "load_15"].iter().fold(
+            self.lwm_banner(self.lwm_label("title_loadavg")),
+            |out, id| out + &self.lwm_render_row(id, loads.next().unwrap_or("n/a"), is_color),
+        )
+    }
+
+    // VM sysctls that explain swap and commit behaviour, n/a when they
+    // can't be read (e.g. in a restricted container)
+    fn lwm_render_sysctl(&self, is_color: bool) -> String {
+        let mut out = self.lwm_banner(self.lwm_label("title_sysctl"));
+
+        ["swappiness", "overcommit_memory", "overcommit_ratio"]
+            .iter()
+            .for_each(|e| {
+                let value = fs::read_to_string(format!("{SYSCTL_VM_PATH}/{e}"))
+                    .map_or("n/a".to_string(), |e| e.trim().to_string());
+                out += &self.lwm_render_row(&format!("vm_{e}"), &value, is_color);
+            });
+
+        out
+    }
+
+    // vm.dirty_ratio, None when it can't be read or is 0 (vm.dirty_bytes
+    // is set instead, so there's no ratio to compare to)
+    fn lwm_dirty_ratio(&self) -> Option<u64> {
+        fs::read_to_string(format!("{SYSCTL_VM_PATH}/dirty_ratio"))
+            .ok()?
+            .trim()
+            .parse()
+            .ok()
+            .filter(|e| *e > 0)
+    }
+
+    // Dirty memory as a percentage of available memory, next to the
+    // vm.dirty_ratio at which the kernel starts throttling writers
+    fn lwm_render_dirty(&self, ratio: Option<u64>, unit: LwmUnit, is_color: bool) -> String {
+        let percent = self.lwm_percent(self.dirty, self.mem_avail);
+        let mut out = self.lwm_render_section(
+            "title_dirty",
+            &[("dirty", self.dirty), ("writeback", self.writeback)],
+            unit,
+            is_color,
+        );
+        out += &self.lwm_render_row("dirty_percent", &format!("{percent:.1}%"), is_color);
+        out += &self.lwm_render_row(
+            "vm_dirty_ratio",
+            &ratio.map_or("n/a".to_string(), |e| format!("{e}%")),
+            is_color,
+        );
+
+        if ratio.is_some_and(|e| percent >= e as f64 * DIRTY_NEAR) {
+            let note = self.lwm_label("dirty_near");
+            out += &if is_color {
+                let color = match self.palette {
+                    LwmPalette::Default => YELLOW_COLOR,
+                    LwmPalette::Colorblind => ORANGE_COLOR,
+                };
+                format!("{color}{note}{END_COLOR}\n")
+            } else {
+                format!("{note}\n")
+            };
+        }
+
+        out
+    }
+
+    // Nothing at all without ZFS
+    fn lwm_render_zfs_arc(&self, unit: LwmUnit, is_color: bool) -> String {
+        let Some((size, max)) = self.lwm_read_arcstats() else {
+            return String::new();
+        };
+
+        self.lwm_render_section(
+            "title_zfs_arc",
+            &[("arc_size", size), ("arc_max", max)],
+            unit,
+            is_color,
+        ) + &format!("{}\n", self.lwm_label("arc_note"))
+    }
+
+    fn lwm_render_swaps(&self, swaps: &[LwmSwap], unit: LwmUnit, is_color: bool) -> String {
+        let mut out = self.lwm_banner(self.lwm_label("title_swaps"));
+
+        if swaps.is_empty() {
+            return out + &format!("* {}\n", self.lwm_label("swap_none"));
         }
+
+        let rows: Vec<[String; 5]> = swaps
+            .iter()
+            .map(|e| {
+                [
+                    e.filename.clone(),
+                    e.kind.clone(),
+                    self.lwm_fmt_size(e.size, unit),
+                    self.lwm_fmt_size(e.used, unit),
+                    e.priority.to_string(),
+                ]
+            })
+            .collect();
+
+        // Widest cell of every column, header included
+        let header = [
+            "swap_filename",
+            "swap_type",
+            "swap_size",
+            "swap_usage",
+            "swap_priority",
+        ]
+        .map(|e| self.lwm_label(e));
+        let mut widths = header.map(|e| e.chars().count());
+        rows.iter().for_each(|row| {
+            row.iter()
+                .enumerate()
+                .for_each(|(i, e)| widths[i] = widths[i].max(e.chars().count()))
+        });
+
+        let header = header
+            .iter()
+            .enumerate()
+            .map(|(i, e)| format!("{:<w$}", e, w = widths[i]))
+            .collect::<Vec<_>>()
+            .join("  ");
+        out += &if is_color {
+            format!("{WHITE_COLOR}{}{END_COLOR}\n", header.trim_end())
+        } else {
+            format!("{}\n", header.trim_end())
+        };
+
+        rows.iter().for_each(|row| {
+            let line = row
+                .iter()
+                .enumerate()
+                .map(|(i, e)| format!("{:<w$}", e, w = widths[i]))
+                .collect::<Vec<_>>()
+                .join("  ");
+            out += &format!("{}\n", line.trim_end());
+        });
+
+        out
+    }
+
+    fn lwm_print_swaps(&self, unit: LwmUnit, is_color: bool) {
+        self.lwm_write(&self.lwm_render_swaps(&self.lwm_read_swaps(), unit, is_color));
+    }
+
+    // Buckets under min_percent of the total are folded into Other
+    fn lwm_render_breakdown(&self, unit: LwmUnit, is_color: bool, min_percent: f64) -> String {
+        let mut buckets = vec![
+            ("anon_pages", self.anon_pages),
+            ("page_cache", self.cached),
+            ("buffers", self.buffers),
+            ("slab", self.slab),
+            ("kernel_stack", self.kernel_stack),
+            ("page_tables", self.page_tables),
+            ("hugetlb", self.hugetlb),
+            ("mem_free", self.mem_free),
+        ];
+
+        // Whatever isn't accounted for by the buckets above
+        let accounted: u64 = buckets.iter().map(|e| e.1).sum();
+        let mut other = self.mem_total.saturating_sub(accounted);
+        buckets.retain(|(_, value)| {
+            let is_kept = self.lwm_percent(*value, self.mem_total) >= min_percent;
+            if !is_kept {
+                other += value;
+            }
+            is_kept
+        });
+        buckets.push(("other", other));
+        // Largest first. The sort is stable, equal buckets keep the order
+        // above so repeated runs always print the same thing
+        buckets.sort_by_key(|e| std::cmp::Reverse(e.1));
+
+        let mut out = self.lwm_banner(self.lwm_label("title_breakdown"));
+
+        buckets.iter().for_each(|(id, value)| {
+            let percent = self.lwm_fmt_percent(*value, self.mem_total, 1);
+
+            out += &if is_color {
+                format!(
+                    "* {WHITE_COLOR}{}{END_COLOR}: {} ({}%)\n",
+                    self.lwm_link_label(id, self.lwm_label(id)),
+                    self.lwm_fmt_size(*value, unit),
+                    percent
+                )
+            } else {
+                format!(
+                    "* {}: {} ({}%)\n",
+                    self.lwm_label(id),
+                    self.lwm_fmt_size(*value, unit),
+                    percent
+                )
+            };
+        });
+
+        out
+    }
+
+    fn lwm_print_breakdown(&self, unit: LwmUnit, is_color: bool, min_percent: f64) {
+        self.lwm_write(&self.lwm_render_breakdown(unit, is_color, min_percent));
     }
 }
 
@@ -406,34 +2725,871 @@ fn main() {
     let mut lwm = Lwm::new();
     let lwm_args = LwmArgs::parse();
 
+    if let Some(flag) = lwm_args.lwm_format_conflict() {
+        LwmArgs::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                format!(
+                    "{flag} has its own layout, it can't be used with {}",
+                    lwm_args.lwm_format_flag()
+                ),
+            )
+            .exit();
+    }
+
+    if lwm_args.selftest {
+        process::exit(if selftest::lwm_selftest() { 0 } else { 1 });
+    }
+
+    if let Some(iterations) = lwm_args.bench_parse {
+        selftest::lwm_bench_parse(iterations);
+        return;
+    }
+
+    lwm.compact = lwm_args.compact;
+    if let Err(e) = lwm.lwm_load_labels(lwm_args.lang, lwm_args.labels.as_deref()) {
+        eprintln!("lwm: {e}");
+        process::exit(1);
+    }
+
+    if lwm_args.help_fields {
+        lwm.lwm_print_help_fields(lwm_args.lwm_color());
+        return;
+    }
+
+    if lwm_args.title_hostname {
+        match lwm.lwm_hostname() {
+            Ok(name) => lwm.title = Some(name),
+            Err(e) => {
+                eprintln!("lwm: {e}");
+                process::exit(1);
+            }
+        }
+    } else {
+        lwm.title = lwm_args.title.clone();
+    }
+
+    lwm.used_mode = lwm_args.used_mode;
+    lwm.normalized = lwm_args.normalized.then_some(lwm_args.precision);
+    lwm.auto_unit = lwm_args.auto_unit;
+    lwm.with_bytes = lwm_args.with_bytes;
+    lwm.from_journal = lwm_args.from_journal.clone();
+    lwm.groups = !lwm_args.no_groups;
+    lwm.links = lwm_args.links;
+    if !lwm_args.exact {
+        match lwm.lwm_parse_size(&lwm_args.swap_noise_floor) {
+            Some(size) => lwm.swap_floor = (size / 1024.0) as u64,
+            None => {
+                eprintln!(
+                    "lwm: --swap-noise-floor: invalid size {}",
+                    lwm_args.swap_noise_floor
+                );
+                process::exit(1);
+            }
+        }
+    }
+
     // Query for the requested fields
-    lwm.lwm_attach_values();
-
-    if lwm_args.all {
-        lwm.lwm_print_all(lwm_args.binary, lwm_args.friendly, !lwm_args.no_color);
-    } else if lwm_args.bytes {
-        lwm.lwm_print_to_size(TO_B, !lwm_args.no_color);
-    } else if lwm_args.kilo {
-        lwm.lwm_print_to_size(TO_KB, !lwm_args.no_color);
-    } else if lwm_args.kibi {
-        lwm.lwm_print_to_size(TO_KiB, !lwm_args.no_color);
-    } else if lwm_args.mega {
-        lwm.lwm_print_to_size(TO_MB, !lwm_args.no_color);
-    } else if lwm_args.mibi {
-        lwm.lwm_print_to_size(TO_MiB, !lwm_args.no_color);
-    } else if lwm_args.giga {
-        lwm.lwm_print_to_size(TO_GB, !lwm_args.no_color);
-    } else if lwm_args.gibi {
-        lwm.lwm_print_to_size(TO_GiB, !lwm_args.no_color);
-    } else if lwm_args.tera {
-        lwm.lwm_print_to_size(TO_TB, !lwm_args.no_color);
-    } else if lwm_args.tibi {
-        lwm.lwm_print_to_size(TO_TiB, !lwm_args.no_color);
-    } else if lwm_args.peta {
-        lwm.lwm_print_to_size(TO_PB, !lwm_args.no_color);
-    } else if lwm_args.pibi {
-        lwm.lwm_print_to_size(TO_PiB, !lwm_args.no_color);
+    if let Err(e) = lwm.lwm_attach_values() {
+        eprintln!("lwm: {e}");
+        process::exit(1);
+    }
+
+    if lwm_args.round_trip_check {
+        process::exit(if selftest::lwm_round_trip(&lwm) { 0 } else { 1 });
+    }
+
+    if !lwm_args.quiet {
+        lwm_args
+            .lwm_ignored_flags()
+            .iter()
+            .for_each(|e| eprintln!("lwm: warning: {e}"));
+    }
+
+    let unit = lwm_args.lwm_unit();
+    let format = lwm_args.lwm_format();
+    let is_color = lwm_args.lwm_color();
+
+    if let Some(id) = &lwm_args.explain_unit {
+        match lwm.lwm_render_explain_unit(id, is_color) {
+            Ok(out) => lwm.lwm_write(&out),
+            Err(e) => {
+                eprintln!("lwm: {e}");
+                process::exit(1);
+            }
+        }
+        return;
+    }
+    lwm.color_values = lwm_args.color_values;
+    lwm.palette = lwm_args.palette;
+    lwm.shmem_detail = lwm_args.shmem_detail;
+
+    if let Some(expr) = &lwm_args.assert {
+        match lwm.lwm_assert(expr) {
+            Ok(None) => return,
+            Ok(Some(why)) => {
+                eprintln!("lwm: assertion failed: {expr} ({why})");
+                process::exit(1);
+            }
+            Err(e) => {
+                eprintln!("lwm: --assert: {e}");
+                process::exit(2);
+            }
+        }
+    }
+
+    if let Some(which) = lwm_args.percent_only {
+        let percent = match which {
+            LwmPercent::Mem => lwm.lwm_floor_percent(lwm.mem_used, lwm.mem_total, 0),
+            LwmPercent::Swap => lwm.lwm_floor_percent(lwm.swap_used, lwm.swap_total, 0),
+        };
+
+        lwm.lwm_write(&format!("{:.0}\n", percent));
+        return;
+    }
+
+    if lwm_args.syslog {
+        if let Err(e) = lwm.lwm_syslog(unit, lwm_args.syslog_facility, &lwm_args.syslog_tag) {
+            eprintln!("lwm: {e}");
+            process::exit(1);
+        }
+
+        if !lwm_args.also_stdout {
+            return;
+        }
+    }
+
+    // Escapes in a file or a pipe would only get in the way
+    lwm.clear = lwm_args.clear && std::io::stdout().is_terminal() && lwm_args.lwm_escapes();
+
+    if let Some(pid) = lwm_args.pid {
+        match lwm.lwm_read_proc(pid) {
+            Ok(Some(proc)) => lwm.lwm_print_proc(&proc, unit, is_color),
+            Ok(None) => {
+                eprintln!("lwm: no process with PID {pid}");
+                process::exit(1);
+            }
+            Err(e) => {
+                eprintln!("lwm: {e}");
+                process::exit(1);
+            }
+        }
+    } else if let Some(pids) = &lwm_args.compare {
+        if let Err(e) = lwm.lwm_print_compare(pids[0], pids[1], unit, is_color) {
+            eprintln!("lwm: {e}");
+            process::exit(1);
+        }
+    } else if let Some(path) = &lwm_args.delta_since {
+        let src = fs::read_to_string(path).unwrap_or_else(|e| {
+            eprintln!("lwm: {path}: {e}");
+            process::exit(1);
+        });
+
+        // Keys the snapshot lacks count as zero, like they do for a live read
+        let mut before = Lwm::new();
+        before.used_mode = lwm.used_mode;
+        before.lwm_parse(&src);
+        lwm.lwm_print_diff(
+            lwm.lwm_label("title_delta"),
+            &lwm.lwm_delta_rows(&before),
+            unit,
+            is_color,
+        );
+    } else if let Some(paths) = &lwm_args.sum {
+        if let Err(e) = lwm.lwm_print_sum(paths, unit, is_color) {
+            eprintln!("lwm: {e}");
+            process::exit(1);
+        }
+    } else if let Some(paths) = &lwm_args.batch {
+        match lwm.lwm_render_batch(paths, unit, is_color, lwm_args.wide) {
+            Ok(out) => lwm.lwm_write(&out),
+            Err(e) => {
+                eprintln!("lwm: {e}");
+                process::exit(1);
+            }
+        }
+    } else if lwm_args.breakdown {
+        lwm.lwm_print_breakdown(unit, is_color, lwm_args.min_percent);
+    } else if lwm_args.swaps {
+        lwm.lwm_print_swaps(unit, is_color);
     } else {
-        lwm.lwm_print_all(lwm_args.binary, lwm_args.friendly, !lwm_args.no_color);
+        match format {
+            LwmFormat::Influx => {
+                let timestamp = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |e| e.as_nanos());
+
+                match lwm.lwm_influx(timestamp) {
+                    Ok(line) => lwm.lwm_write(&line),
+                    Err(e) => {
+                        eprintln!("lwm: {e}");
+                        process::exit(1);
+                    }
+                }
+            }
+            LwmFormat::Free => lwm.lwm_print_free_compat(unit),
+            LwmFormat::Oneline => lwm.lwm_write(&format!("{}\n", lwm.lwm_oneline(unit))),
+            LwmFormat::Bare => lwm.lwm_print_bare(unit),
+            LwmFormat::Pretty => {
+                let dirty_ratio = lwm.lwm_dirty_ratio();
+                let render = |lwm: &Lwm, is_color: bool| {
+                    let mut out = lwm.lwm_render_all(unit, is_color, lwm_args.wide)
+                        + &lwm.lwm_render_sections(unit, is_color);
+
+                    if lwm_args.show_both_avail {
+                        out += &lwm.lwm_render_both_avail(unit, is_color);
+                    }
+
+                    if lwm_args.hardware_total {
+                        out += &lwm.lwm_render_hardware(unit, is_color);
+                    }
+
+                    if lwm_args.iomem_reserved {
+                        let src = fs::read_to_string(IOMEM_PATH)
+                            .map_err(|e| format!("{IOMEM_PATH}: {e}"));
+                        out += &lwm.lwm_render_iomem(src, unit, is_color);
+                    }
+
+                    if lwm_args.hotplug {
+                        out += &lwm.lwm_render_hotplug(unit, is_color);
+                    }
+
+                    if lwm_args.loadavg {
+                        out += &lwm.lwm_render_loadavg(is_color);
+                    }
+
+                    if lwm_args.sysctl {
+                        out += &lwm.lwm_render_sysctl(is_color);
+                    }
+
+                    if lwm_args.dirty {
+                        out += &lwm.lwm_render_dirty(dirty_ratio, unit, is_color);
+                    }
+
+                    if lwm_args.zfs_arc {
+                        out += &lwm.lwm_render_zfs_arc(unit, is_color);
+                    }
+
+                    if lwm_args.verdict {
+                        out += &lwm.lwm_render_verdict(is_color);
+                    }
+
+                    out
+                };
+                let mut out = render(&lwm, is_color);
+
+                // Lines that would wrap get the compact layout instead, measured
+                // without colors since escapes take no room on screen
+                let width = lwm.lwm_term_width();
+                let widest = render(&lwm, false)
+                    .lines()
+                    .map(|e| e.chars().count())
+                    .max()
+                    .unwrap_or(0);
+                if !lwm.compact && width.is_some_and(|e| widest > e) {
+                    lwm.compact = true;
+                    // Loaded fine just above, so this can't fail now
+                    let _ = lwm.lwm_load_labels(lwm_args.lang, lwm_args.labels.as_deref());
+                    out = render(&lwm, is_color);
+                }
+
+                lwm.lwm_write(&out);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A small machine with round numbers, English labels
+    fn lwm_sample() -> Lwm {
+        let mut lwm = Lwm::new();
+        lwm.lwm_load_labels(LwmLang::En, None).unwrap();
+        lwm.lwm_parse(
+            "MemTotal:       16384 kB\n\
+             MemFree:         4096 kB\n\
+             MemAvailable:    8192 kB\n\
+             Buffers:         1024 kB\n\
+             Cached:          2048 kB\n\
+             SwapCached:         0 kB\n\
+             SwapTotal:       2048 kB\n\
+             SwapFree:        1024 kB\n\
+             Shmem:            512 kB\n",
+        );
+        lwm
+    }
+
+    const FRIENDLY: LwmUnit = LwmUnit::Friendly {
+        binary: true,
+        round: true,
+        short: false,
+    };
+
+    #[test]
+    fn render_all_plain() {
+        let out = lwm_sample().lwm_render_all(LwmUnit::Size(TO_MiB), false, false);
+
+        assert_eq!(
+            out,
+            "======================\n\
+             | Memory Information |\n\
+             ======================\n\
+             * Total Memory: 16\n\
+             * Free Memory: 4\n\
+             * Avail Memory: 8\n\
+             * Reclaimable: 4\n\
+             * Used Memory: 8\n\
+             * Buffered: 1\n\
+             * Total Swap: 2\n\
+             * Free Swap: 1\n\
+             * Cached Swap: 0\n\
+             * Used Swap: 1\n\
+             * Total ZSwap: 0\n\
+             * Commit ZSwap: 0\n\
+             * ZSwap Ratio: n/a\n\
+             * Shared Memory: 0\n"
+        );
+    }
+
+    #[test]
+    fn render_all_friendly_wide() {
+        let out = lwm_sample().lwm_render_all(FRIENDLY, false, true);
+
+        assert!(out.contains("* Total Memory: 16.0MiB\n"));
+        assert!(out.contains("* Cached: 2.0MiB\n"));
+        assert!(out.contains("* Buff/Cache: 3.0MiB\n"));
+        assert!(out.contains("* Shared Memory: 512.0KiB\n"));
+    }
+
+    #[test]
+    fn render_color_labels_and_values() {
+        let mut lwm = lwm_sample();
+        let out = lwm.lwm_render_all(FRIENDLY, true, false);
+        assert!(out.contains("* \x1b[1;37mFree Memory\x1b[0m: 4.0MiB\n"));
+
+        lwm.color_values = true;
+        let out = lwm.lwm_render_all(FRIENDLY, true, false);
+        assert!(out.contains("* \x1b[1;37mFree Memory\x1b[0m: \x1b[1;32m4.0MiB\x1b[0m\n"));
+        assert!(out.contains("* \x1b[1;37mUsed Memory\x1b[0m: \x1b[1;33m8.0MiB\x1b[0m\n"));
+        assert!(out.contains("* \x1b[1;37mTotal Memory\x1b[0m: 16.0MiB\n"));
+
+        lwm.palette = LwmPalette::Colorblind;
+        let out = lwm.lwm_render_all(FRIENDLY, true, false);
+        assert!(out.contains("* \x1b[1;37mFree Memory\x1b[0m: \x1b[1;34m4.0MiB\x1b[0m\n"));
+    }
+
+    // Everything that can be colored, rendered with or without colors
+    fn lwm_render_everything(lwm: &Lwm, is_color: bool) -> Vec<String> {
+        vec![
+            lwm.lwm_render_all(FRIENDLY, is_color, false),
+            lwm.lwm_render_all(FRIENDLY, is_color, true),
+            lwm.lwm_render_sections(FRIENDLY, is_color),
+            lwm.lwm_render_breakdown(FRIENDLY, is_color, 0.0),
+            lwm.lwm_render_verdict(is_color),
+            lwm.lwm_render_diff("Diff", &[("mem_free", 2048, 4096)], FRIENDLY, is_color),
+            lwm.lwm_render_help_fields(is_color),
+            lwm.lwm_render_explain_unit("mem_total", is_color).unwrap(),
+        ]
+    }
+
+    #[test]
+    fn no_color_has_no_escapes() {
+        let mut lwm = lwm_sample();
+        lwm.color_values = true;
+
+        lwm_render_everything(&lwm, false)
+            .iter()
+            .for_each(|out| assert!(!out.contains('\x1b'), "{out:?}"));
+    }
+
+    #[test]
+    fn color_escapes_are_closed() {
+        let mut lwm = lwm_sample();
+        lwm.color_values = true;
+
+        lwm_render_everything(&lwm, true).iter().for_each(|out| {
+            assert!(out.contains("\x1b["), "{out:?}");
+            assert_eq!(
+                out.matches("\x1b[").count(),
+                2 * out.matches(END_COLOR).count(),
+                "{out:?}"
+            );
+            out.lines()
+                .filter(|e| e.starts_with("* "))
+                .for_each(|e| assert!(e.starts_with("* \x1b[1;37m") && e.contains("\x1b[0m: ")));
+        });
+    }
+
+    #[test]
+    fn dirty_near_the_ratio() {
+        let mut lwm = lwm_sample();
+        lwm.dirty = 820;
+
+        let out = lwm.lwm_render_dirty(Some(20), FRIENDLY, false);
+        assert!(out.contains("* Dirty: 820.0KiB\n"));
+        assert!(out.contains("* Dirty Of Available: 10.0%\n"));
+        assert!(out.contains("* Dirty Ratio: 20%\n"));
+        assert!(!out.contains("throttled"));
+
+        lwm.dirty = 1400;
+        let out = lwm.lwm_render_dirty(Some(20), FRIENDLY, false);
+        assert!(out.contains("throttled"));
+
+        let out = lwm.lwm_render_dirty(None, FRIENDLY, false);
+        assert!(out.contains("* Dirty Ratio: n/a\n"));
+        assert!(!out.contains("throttled"));
+    }
+
+    #[test]
+    fn both_avail_shows_the_difference() {
+        let out = lwm_sample().lwm_render_both_avail(FRIENDLY, false);
+
+        assert!(out.contains("* MemAvailable: 8.0MiB\n"));
+        assert!(out.contains("* Classic Estimate: 7.0MiB\n"));
+        assert!(out.contains("* Difference: -1.0MiB\n"));
+    }
+
+    #[test]
+    fn both_avail_prefers_kreclaimable() {
+        let mut lwm = lwm_sample();
+        lwm.s_reclaimable = 512;
+        let out = lwm.lwm_render_both_avail(FRIENDLY, false);
+        assert!(out.contains("* Classic Estimate: 7.5MiB\n"));
+
+        lwm.has_kreclaimable = true;
+        lwm.k_reclaimable = 1024;
+        let out = lwm.lwm_render_both_avail(FRIENDLY, false);
+        assert!(out.contains("* Classic Estimate: 8.0MiB\n"));
+    }
+
+    #[test]
+    fn links_wrap_field_labels() {
+        let mut lwm = lwm_sample();
+        lwm.links = true;
+        let link = |label| format!("\x1b]8;;{MEMINFO_DOC_URL}\x1b\\{label}\x1b]8;;\x1b\\");
+
+        let out = lwm.lwm_render_all(FRIENDLY, true, false);
+        assert!(out.contains(&format!(
+            "* {WHITE_COLOR}{}{END_COLOR}: 4.0MiB\n",
+            link("Free Memory")
+        )));
+        // Not a meminfo field
+        assert!(out.contains(&format!("* {WHITE_COLOR}ZSwap Ratio{END_COLOR}: n/a\n")));
+
+        lwm.auto_unit = true;
+        let out = lwm.lwm_render_all(FRIENDLY, true, false);
+        assert!(out.contains(&format!(
+            "* {WHITE_COLOR}{}  {END_COLOR}",
+            link("Free Memory")
+        )));
+
+        assert!(!lwm.lwm_render_all(FRIENDLY, false, false).contains('\x1b'));
+    }
+
+    #[test]
+    fn iomem_regions() {
+        let lwm = lwm_sample();
+        let src = "00000000-00000fff : Reserved\n\
+                   00001000-0009fbff : System RAM\n\
+                   00100000-00ffffff : System RAM\n\
+                   \x20 00200000-002fffff : Kernel code\n\
+                   \x20 00300000-0033ffff : Kernel data\n\
+                   \x20 00400000-004fffff : reserved\n\
+                   fec00000-fec003ff : reserved\n";
+
+        assert_eq!(
+            lwm.lwm_parse_iomem(src),
+            Ok((635 + 15360, 4 + 1, 1024 + 256))
+        );
+        assert!(lwm
+            .lwm_parse_iomem("00000000-00000000 : Reserved\n00000000-00000000 : System RAM\n")
+            .is_err());
+
+        let out = lwm.lwm_render_iomem(Err("denied".to_string()), FRIENDLY, false);
+        assert!(out.ends_with("Not available: denied\n"));
+    }
+
+    #[test]
+    fn formats_conflict_with_own_layouts() {
+        let conflict =
+            |args: &[&str]| LwmArgs::parse_from([&["lwm"], args].concat()).lwm_format_conflict();
+
+        assert_eq!(
+            conflict(&["--pid", "1", "--output-format", "influx"]),
+            Some("--pid")
+        );
+        assert_eq!(conflict(&["--breakdown", "--bare"]), Some("--breakdown"));
+        assert_eq!(
+            conflict(&["--breakdown", "--output-format", "pretty"]),
+            None
+        );
+        assert_eq!(conflict(&["--syslog", "--also-stdout", "--bare"]), None);
+        assert_eq!(conflict(&["--output-format", "oneline"]), None);
+    }
+
+    #[test]
+    fn render_bare_and_free_compat() {
+        let lwm = lwm_sample();
+
+        assert_eq!(
+            lwm.lwm_render_bare(LwmUnit::Size(TO_KiB)),
+            "16384 4096 8192 8192 1024 2048 1024 0 1024 0 0 512\n"
+        );
+        assert_eq!(
+            lwm.lwm_render_free_compat(LwmUnit::Size(TO_MiB)),
+            "               total        used        free      shared  buff/cache   available\n\
+             Mem:              16           8           4           0           3           8\n\
+             Swap:              2           1           1\n"
+        );
+    }
+
+    #[test]
+    fn render_groups_subheaders() {
+        let mut lwm = lwm_sample();
+        lwm.groups = true;
+        let out = lwm.lwm_render_all(FRIENDLY, false, true);
+
+        assert!(out.contains("======================\n-- RAM --\n* Total Memory: 16.0MiB\n"));
+        assert!(out.contains("* Used Memory: 8.0MiB\n\n-- Cache --\n* Buffered: 1.0MiB\n"));
+        assert!(out.contains("* Buff/Cache: 3.0MiB\n\n-- Swap --\n"));
+        assert!(out.contains("* ZSwap Ratio: n/a\n\n-- Shared --\n* Shared Memory: 512.0KiB\n"));
+        assert_eq!(out.matches("-- ").count(), 4);
+    }
+
+    #[test]
+    fn render_compact_drops_the_box() {
+        let mut lwm = lwm_sample();
+        lwm.compact = true;
+        lwm.lwm_load_labels(LwmLang::En, None).unwrap();
+
+        let out = lwm.lwm_render_all(FRIENDLY, false, false);
+        assert!(out.starts_with("Memory\n* Tot: 16.0MiB\n* Free: 4.0MiB\n"));
+    }
+
+    #[test]
+    fn breakdown_ties_keep_bucket_order() {
+        let mut lwm = Lwm::new();
+        lwm.lwm_parse("MemTotal: 3072 kB\nMemFree: 1024 kB\nAnonPages: 1024 kB\n");
+
+        let ids: Vec<String> = lwm
+            .lwm_render_breakdown(LwmUnit::Size(TO_KiB), false, 0.0)
+            .lines()
+            .skip(3)
+            .map(|e| e[2..e.find(':').unwrap()].to_string())
+            .collect();
+
+        assert_eq!(
+            ids,
+            [
+                "anon_pages",
+                "mem_free",
+                "other",
+                "page_cache",
+                "buffers",
+                "slab",
+                "kernel_stack",
+                "page_tables",
+                "hugetlb"
+            ]
+        );
+    }
+
+    #[test]
+    fn assert_sizes_and_percentages() {
+        let lwm = lwm_sample();
+
+        assert_eq!(lwm.lwm_parse_size("2GiB"), Some(2147483648.0));
+        assert_eq!(lwm.lwm_parse_size("1.5 GB"), Some(1500000000.0));
+        assert_eq!(lwm.lwm_parse_size("512M"), Some(536870912.0));
+        assert_eq!(lwm.lwm_parse_size("100"), Some(100.0));
+        assert_eq!(lwm.lwm_parse_size("2XB"), None);
+
+        assert_eq!(lwm.lwm_assert("mem_avail > 4MiB"), Ok(None));
+        assert_eq!(lwm.lwm_assert("mem_avail>=8MiB"), Ok(None));
+        assert_eq!(
+            lwm.lwm_assert("mem_avail > 8MiB"),
+            Ok(Some("mem_avail is 8.0MiB".to_string()))
+        );
+        assert_eq!(lwm.lwm_assert("swap_used <= 50%"), Ok(None));
+        assert_eq!(
+            lwm.lwm_assert("mem_used < 50%"),
+            Ok(Some("mem_used is 50.0%".to_string()))
+        );
+        assert!(lwm.lwm_assert("mem_avail ~ 2GiB").is_err());
+        assert!(lwm.lwm_assert("nope > 2GiB").is_err());
+    }
+
+    #[test]
+    fn breakdown_folds_small_buckets() {
+        let mut lwm = Lwm::new();
+        lwm.lwm_parse("MemTotal: 1000 kB\nMemFree: 900 kB\nAnonPages: 40 kB\nSlab: 60 kB\n");
+
+        let out = lwm.lwm_render_breakdown(LwmUnit::Size(TO_KiB), false, 5.0);
+        assert_eq!(
+            out.lines().skip(3).collect::<Vec<_>>(),
+            [
+                "* mem_free: 900 (90.0%)",
+                "* slab: 60 (6.0%)",
+                "* other: 40 (4.0%)"
+            ]
+        );
+    }
+
+    #[test]
+    fn normalized_values_are_fractions() {
+        let mut lwm = lwm_sample();
+        lwm.normalized = Some(2);
+
+        assert_eq!(
+            lwm.lwm_render_bare(FRIENDLY),
+            "1.00 0.25 0.50 0.50 0.06 1.00 0.50 0.00 0.50 0.00 0.00 0.03\n"
+        );
+
+        lwm.swap_total = 0;
+        assert_eq!(lwm.lwm_fmt_value("swap_used", 1024, FRIENDLY), "0.00");
+    }
+
+    #[test]
+    fn verdict_names_the_condition() {
+        let mut lwm = lwm_sample();
+        assert_eq!(lwm.lwm_render_verdict(false), "Status: OK\n");
+
+        lwm.swap_used = 1800;
+        assert_eq!(
+            lwm.lwm_render_verdict(false),
+            "Status: CRITICAL (swapping heavily)\n"
+        );
+
+        lwm.swap_used = 0;
+        lwm.mem_avail = 2048;
+        assert_eq!(
+            lwm.lwm_render_verdict(false),
+            "Status: WARNING (low memory)\n"
+        );
+    }
+
+    #[test]
+    fn auto_unit_aligns_numbers_and_suffixes() {
+        let mut lwm = lwm_sample();
+        lwm.auto_unit = true;
+
+        let out = lwm.lwm_render_all(FRIENDLY, false, false);
+        assert!(out.contains("* Total Memory    16.0 MiB\n"));
+        assert!(out.contains("* Buffered         1.0 MiB\n"));
+        assert!(out.contains("* Total ZSwap        0 B\n"));
+        assert!(out.contains("* ZSwap Ratio      n/a\n"));
+        assert!(out.contains("* Shared Memory  512.0 KiB\n"));
+    }
+
+    #[test]
+    fn with_bytes_shows_the_exact_count() {
+        let mut lwm = lwm_sample();
+        lwm.with_bytes = true;
+
+        let out = lwm.lwm_render_all(FRIENDLY, false, false);
+        assert!(out.contains("* Total Memory: 16.0MiB (16777216 B)\n"));
+
+        let out = lwm.lwm_render_all(LwmUnit::Size(TO_KiB), false, false);
+        assert!(out.contains("* Total Memory: 16384\n"));
+
+        // Decimal units still read meminfo's kB as 1024 bytes
+        let decimal = LwmUnit::Friendly {
+            binary: false,
+            round: true,
+            short: false,
+        };
+        let out = lwm.lwm_render_all(decimal, false, false);
+        assert!(out.contains("* Total Memory: 16.8MB (16777216 B)\n"));
+    }
+
+    #[test]
+    fn no_round_keeps_full_precision() {
+        let lwm = Lwm::new();
+
+        assert_eq!(
+            lwm.lwm_conv_to_hbytes(1234567.0, false, false, false),
+            "1.234567MB"
+        );
+        assert_eq!(
+            lwm.lwm_conv_to_hbytes(16777216.0, true, false, false),
+            "16MiB"
+        );
+        assert_eq!(
+            lwm.lwm_conv_to_hbytes(1234567.0, false, true, false),
+            "1.2MB"
+        );
+    }
+
+    #[test]
+    fn swap_noise_shows_as_zero() {
+        let mut lwm = lwm_sample();
+        lwm.swap_floor = 1024;
+
+        assert_eq!(lwm.lwm_fmt_shown("swap_used", 4, FRIENDLY), "0B");
+        assert_eq!(lwm.lwm_fmt_shown("swap_used", 1024, FRIENDLY), "1.0MiB");
+        assert_eq!(lwm.lwm_fmt_shown("swap_free", 4, FRIENDLY), "4.0KiB");
+        assert_eq!(
+            lwm.lwm_fmt_shown("swap_used", 4, LwmUnit::Size(TO_KiB)),
+            "4"
+        );
+
+        lwm.with_bytes = true;
+        assert_eq!(lwm.lwm_fmt_shown("swap_used", 4, FRIENDLY), "0B (4096 B)");
+    }
+
+    #[test]
+    fn render_diff_shows_the_change() {
+        let lwm = lwm_sample();
+        let out = lwm.lwm_render_diff("Diff", &[("mem_free", 2048, 4096)], FRIENDLY, false);
+
+        assert_eq!(
+            out,
+            "========\n| Diff |\n========\n* Free Memory: 2.0MiB -> 4.0MiB (+2.0MiB ^)\n"
+        );
+    }
+
+    #[test]
+    fn swap_keys_match_exactly() {
+        let src = "Cached:          1000 kB\n\
+                   SwapCached:        20 kB\n\
+                   SwapTotal:       4000 kB\n\
+                   SwapFree:        3000 kB\n\
+                   Swap:              99 kB\n";
+        let mut lwm = Lwm::new();
+        lwm.lwm_parse(src);
+
+        assert_eq!(lwm.cached, 1000);
+        assert_eq!(lwm.swap_cached, 20);
+        assert_eq!(lwm.swap_total, 4000);
+        assert_eq!(lwm.swap_free, 3000);
+        assert_eq!(lwm.swap_used, 1000);
+        assert_eq!(lwm.lwm_get_value(src, "Swap:"), 99);
+    }
+
+    #[test]
+    fn unit_suffix_casing() {
+        let mut lwm = Lwm::new();
+        lwm.lwm_parse("MemTotal: 16384 KB\nMemFree: 4096 kb\nBuffers: 1048576 B\n");
+
+        assert_eq!(lwm.mem_total, 16384);
+        assert_eq!(lwm.mem_free, 4096);
+        assert_eq!(lwm.buffers, 1024);
+        assert!(lwm
+            .lwm_check_required("MemTotal: 16384 KB\nMemFree: 4096 kb\n")
+            .is_ok());
+        assert_eq!(lwm.lwm_parse_value(" 16384 MB"), None);
+    }
+
+    #[test]
+    fn truncated_lines_dont_panic() {
+        let src = "MemTotal:       16384 kB\n\
+                   MemFree:         8192 kB\n\
+                   MemAvailable:   12288 kB\n\
+                   Buffers:          51\u{0}2 kB\n\
+                   Cached:          40\n\
+                   SwapTotal:       4096 kB\n\
+                   SwapFr";
+        let mut lwm = Lwm::new();
+        lwm.lwm_parse(src);
+
+        assert!(lwm.lwm_check_required(src).is_ok());
+        assert_eq!(lwm.mem_total, 16384);
+        assert_eq!(lwm.mem_used, 4096);
+        assert_eq!(lwm.buffers, 0);
+        assert_eq!(lwm.cached, 40);
+        assert_eq!(lwm.swap_free, 0);
+        assert_eq!(lwm.swap_used, 4096);
+    }
+
+    #[test]
+    fn truncated_required_line_is_an_error() {
+        let lwm = Lwm::new();
+
+        assert!(lwm
+            .lwm_check_required("MemFree: 8 kB\nMemTotal:   16384 k")
+            .is_err());
+        assert!(lwm.lwm_check_required("MemTotal: 16 kB\nMemFr").is_err());
+        assert!(lwm
+            .lwm_check_required("MemTotal: 16 kB\nMemFree: 8 kB")
+            .is_ok());
+    }
+
+    #[test]
+    fn field_table_matches_parser() {
+        LWM_FIELDS.iter().for_each(|(id, _, key, _)| {
+            let mut lwm = Lwm::new();
+
+            // Computed fields name an expression rather than a key
+            if !key.contains(' ') {
+                lwm.lwm_parse(&format!("{key}: 7 kB"));
+                assert_eq!(lwm.lwm_field(id), Some(7), "{id}");
+            } else {
+                assert!(lwm.lwm_field(id).is_some(), "{id}");
+            }
+        });
+    }
+
+    #[test]
+    fn percent_is_never_rounded_up_to_full() {
+        let lwm = Lwm::new();
+
+        assert_eq!(lwm.lwm_fmt_percent(996, 1000, 0), "99");
+        assert_eq!(lwm.lwm_fmt_percent(9999, 10000, 1), "99.9");
+        assert_eq!(lwm.lwm_fmt_percent(1000, 1000, 0), "100");
+        assert_eq!(lwm.lwm_fmt_percent(260, 1000, 0), "26");
+    }
+
+    #[test]
+    fn tiny_percent_is_not_zero() {
+        let lwm = Lwm::new();
+
+        assert_eq!(lwm.lwm_fmt_percent(1, 1000, 0), "<1");
+        assert_eq!(lwm.lwm_fmt_percent(1, 100000, 1), "<0.1");
+        assert_eq!(lwm.lwm_fmt_percent(0, 1000, 1), "0.0");
+        assert_eq!(lwm.lwm_fmt_percent(0, 0, 0), "0");
+    }
+
+    #[test]
+    fn explain_unit_decimal_and_binary() {
+        let out = lwm_sample()
+            .lwm_render_explain_unit("mem_total", false)
+            .unwrap();
+
+        let row = |name: &str| {
+            out.lines()
+                .map(|e| e.split_whitespace().collect::<Vec<_>>())
+                .find(|e| e[0] == name)
+                .unwrap()
+        };
+        assert_eq!(row("KB"), ["KB", "--kilo", "16777", "16777.216"]);
+        assert_eq!(row("KiB"), ["KiB", "--kibi", "16384", "16384.0"]);
+        assert_eq!(row("MiB"), ["MiB", "--mibi", "16", "16.0"]);
+        assert!(lwm_sample().lwm_render_explain_unit("nope", false).is_err());
+    }
+
+    #[test]
+    fn journal_export_values() {
+        let lwm = Lwm::new();
+        let mut export = b"__CURSOR=s=1\nMESSAGE=hello\n\n__CURSOR=s=2\nMEMINFO\n".to_vec();
+        let snapshot = "MemTotal: 16384 kB\nMemFree: 4096 kB\n";
+        export.extend((snapshot.len() as u64).to_le_bytes());
+        export.extend(snapshot.as_bytes());
+        export.extend(b"\nMESSAGE=snapshot\n\n");
+
+        let value = |field| lwm.lwm_journal_value(&mut export.as_slice(), field);
+        assert_eq!(value("MESSAGE").as_deref(), Some("hello"));
+        assert_eq!(value("MEMINFO").as_deref(), Some(snapshot));
+        assert_eq!(value("OTHER"), None);
+    }
+
+    #[test]
+    fn swap_cached_before_cached() {
+        let src = "SwapCached:        20 kB\nCached:          1000 kB\n";
+        let lwm = Lwm::new();
+
+        assert_eq!(lwm.lwm_get_value(src, "Cached:"), 1000);
+        assert_eq!(lwm.lwm_get_value(src, "SwapCached:"), 20);
     }
 }
@@ -0,0 +1,152 @@
+//! Hidden --selftest mode, runs the meminfo parser against embedded
+//! samples and checks the parsed values. Useful where `cargo test`
+//! isn't available. The hidden --bench-parse times the parser on the
+//! same samples, and --round-trip-check checks the friendly formatter
+//! against the size parser on live values.
+
+use super::{Lwm, LwmUnit, LWM_FIELDS};
+use std::hint::black_box;
+use std::time::Instant;
+
+// Name of the sample, its contents, and the fields expected from it
+type LwmFixture = (&'static str, &'static str, &'static [(&'static str, u64)]);
+
+const FIXTURES: [LwmFixture; 4] = [
+    (
+        "old-kernel",
+        include_str!("../fixtures/old-kernel.meminfo"),
+        &[
+            ("mem_total", 3880184),
+            ("mem_avail", 2961036),
+            ("mem_used", 919148),
+            ("swap_used", 35840),
+            ("zswap", 0),
+            ("zswapped", 0),
+            ("hugetlb", 0),
+            ("anon_huge_pages", 278528),
+            ("cma_total", 0),
+            ("cma_free", 0),
+        ],
+    ),
+    (
+        "swapless",
+        include_str!("../fixtures/swapless.meminfo"),
+        &[
+            ("mem_total", 6158152),
+            ("swap_total", 0),
+            ("swap_free", 0),
+            ("swap_used", 0),
+            ("swap_cached", 0),
+            ("percpu", 272),
+        ],
+    ),
+    (
+        "no-zswap",
+        include_str!("../fixtures/no-zswap.meminfo"),
+        &[
+            ("mem_total", 16302140),
+            ("mem_free", 5012464),
+            ("cached", 5517908),
+            ("swap_cached", 12548),
+            ("swap_used", 266752),
+            ("zswap", 0),
+            ("zswapped", 0),
+            ("vmalloc_total", 34359738367),
+        ],
+    ),
+    (
+        "numa-node",
+        include_str!("../fixtures/numa-node.meminfo"),
+        &[
+            ("mem_total", 32832724),
+            ("mem_free", 20458316),
+            ("anon_pages", 2973476),
+            ("shmem", 172600),
+            ("slab", 744696),
+            ("anon_huge_pages", 370688),
+        ],
+    ),
+];
+
+// Print PASS/FAIL per sample, true if all of them passed
+pub fn lwm_selftest() -> bool {
+    FIXTURES.iter().fold(true, |passed, (name, src, expected)| {
+        let mut lwm = Lwm::new();
+        lwm.lwm_parse(src);
+
+        let failures: Vec<String> = expected
+            .iter()
+            .filter_map(|(id, value)| match lwm.lwm_field(id) {
+                Some(e) if e == *value => None,
+                e => Some(format!("{id}: expected {value}, got {e:?}")),
+            })
+            .collect();
+
+        if failures.is_empty() {
+            println!("PASS {name}");
+        } else {
+            println!("FAIL {name}");
+            failures.iter().for_each(|e| println!("  {e}"));
+        }
+
+        passed && failures.is_empty()
+    })
+}
+
+// Parse every sample this many times, then print the total time and
+// the parses per second
+pub fn lwm_bench_parse(iterations: u64) {
+    let mut lwm = Lwm::new();
+    let start = Instant::now();
+    (0..iterations).for_each(|_| {
+        FIXTURES
+            .iter()
+            .for_each(|(_, src, _)| lwm.lwm_parse(black_box(src)))
+    });
+    black_box(&lwm);
+
+    let elapsed = start.elapsed().as_secs_f64();
+    let parses = iterations * FIXTURES.len() as u64;
+    println!("{parses} parses in {elapsed:.3}s");
+    println!("{:.0} parses/s", parses as f64 / elapsed);
+}
+
+// Format every field of the current meminfo as a friendly size (binary
+// and decimal), the way it's displayed, and parse it back. The result has to be within the
+// rounding to one decimal of the unit the value was shown in. Prints
+// PASS/FAIL per field, true if all of them passed
+pub fn lwm_round_trip(lwm: &Lwm) -> bool {
+    LWM_FIELDS.iter().fold(true, |passed, (id, ..)| {
+        let bytes = lwm.lwm_field(id).unwrap_or(0) as f64 * 1024.0;
+
+        let failures: Vec<String> = [1024.0, 1000.0]
+            .iter()
+            .filter_map(|unit| {
+                let friendly = LwmUnit::Friendly {
+                    binary: *unit == 1024.0,
+                    round: true,
+                    short: false,
+                };
+                let shown = lwm.lwm_fmt_size(lwm.lwm_field(id).unwrap_or(0), friendly);
+                let base = (bytes.log10() / f64::log10(*unit)).floor().clamp(0.0, 5.0);
+                let tolerance = 0.05 * unit.powf(base);
+
+                match lwm.lwm_parse_size(&shown) {
+                    Some(e) if (e - bytes).abs() <= tolerance * 1.000001 => None,
+                    e => Some(format!(
+                        "{shown}: expected {bytes} B (+/-{tolerance}), got {e:?}"
+                    )),
+                }
+            })
+            .collect();
+
+        if failures.is_empty() {
+            println!("PASS {id}");
+        } else {
+            println!("FAIL {id}");
+            failures.iter().for_each(|e| println!("  {e}"));
+        }
+
+        passed && failures.is_empty()
+    })
+}